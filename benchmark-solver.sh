@@ -4,6 +4,8 @@ set -euo pipefail
 
 ITERATIONS=100
 DESTINATION="/tmp/solver-benchmark.txt"
+# Extra solver options to benchmark, e.g. SOLVER_FLAGS="--threads 4"
+SOLVER_FLAGS="${SOLVER_FLAGS:-}"
 
 # Prepare input/output files, build binary
 rm -f $DESTINATION
@@ -18,7 +20,7 @@ done
 for _ in $(seq 1 $ITERATIONS); do
     cut -d ',' -f 1 levels.txt | while read LEVEL; do
         echo $LEVEL >> $DESTINATION
-        bash -c "time ./target/release/golf-peaks --steps" < /tmp/levels/$LEVEL.level.txt 2>&1 >> $DESTINATION | sed -n "s/.*0m\([0-9.]*\)s/\1/p" >> $DESTINATION
+        bash -c "time ./target/release/golf-peaks --steps $SOLVER_FLAGS" < /tmp/levels/$LEVEL.level.txt 2>&1 >> $DESTINATION | sed -n "s/.*0m\([0-9.]*\)s/\1/p" >> $DESTINATION
     done
 done;
 
@@ -0,0 +1,97 @@
+use crate::solver::Solution;
+use crate::{Card, Direction, KnownMoves, Location, MoveOutcome, Terrain, Tile, DIRECTIONS};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+struct State {
+    position: Location,
+    remaining: Vec<i32>,
+    steps: i32,
+    // The state before this one, and the move that was played from it
+    previous: Option<(usize, Card, Direction, MoveOutcome)>,
+}
+
+// A* search over positions and remaining cards, ordered by the steps taken so
+// far plus the relaxed steps to the hole. The relaxed distance never
+// overestimates, so the first time the hole comes off the queue it's reached
+// in the fewest steps possible. keep_going is asked before each state is
+// expanded, and stops the search when it says no
+pub fn fewest_steps(
+    map: &HashMap<Location, Tile>,
+    start: Location,
+    cards: &[Card],
+    card_counts: &[i32],
+    transitions: &KnownMoves,
+    steps_to_hole: &HashMap<Location, i32>,
+    keep_going: &mut impl FnMut() -> bool,
+) -> Option<Solution> {
+    let mut states = vec![State {
+        position: start,
+        remaining: card_counts.to_vec(),
+        steps: 0,
+        previous: None,
+    }];
+    let mut fewest_steps: HashMap<(Location, Vec<i32>), i32> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    if let Some(estimate) = steps_to_hole.get(&start) {
+        queue.push(Reverse((*estimate, 0)));
+    }
+
+    while let Some(Reverse((_, s))) = queue.pop() {
+        if !keep_going() {
+            return None;
+        }
+        let state = &states[s];
+        if fewest_steps
+            .get(&(state.position, state.remaining.clone()))
+            .is_some_and(|steps| *steps < state.steps)
+        {
+            continue;
+        }
+        if map.get(&state.position).unwrap().terrain == Terrain::Hole {
+            let mut solution = Vec::new();
+            let mut s = s;
+            while let Some((previous, card, direction, outcome)) = states[s].previous {
+                solution.insert(0, (card, direction, outcome));
+                s = previous;
+            }
+            return Some(solution);
+        }
+
+        let (position, remaining, steps) = (state.position, state.remaining.clone(), state.steps);
+        for (i, card) in cards.iter().enumerate() {
+            if remaining[i] == 0 {
+                continue;
+            }
+            for direction in DIRECTIONS.iter() {
+                let outcome = match transitions.get(&(position, *card, *direction)) {
+                    Some(Ok(outcome)) => *outcome,
+                    _ => continue,
+                };
+                let estimate = match steps_to_hole.get(&outcome.position) {
+                    Some(estimate) => *estimate,
+                    None => continue,
+                };
+                let mut remaining = remaining.clone();
+                remaining[i] -= 1;
+                let key = (outcome.position, remaining.clone());
+                let next_steps = steps + outcome.steps;
+                if fewest_steps
+                    .get(&key)
+                    .is_some_and(|steps| *steps <= next_steps)
+                {
+                    continue;
+                }
+                fewest_steps.insert(key, next_steps);
+                states.push(State {
+                    position: outcome.position,
+                    remaining,
+                    steps: next_steps,
+                    previous: Some((s, *card, *direction, outcome)),
+                });
+                queue.push(Reverse((next_steps + estimate, states.len() - 1)));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,178 @@
+use crate::solver::Solution;
+use crate::transitions::sorted_locations;
+use crate::{Card, Direction, KnownMoves, Location, MoveOutcome, Terrain, Tile, DIRECTIONS};
+use std::collections::HashMap;
+
+// A position, and the cards left (going forwards) or used (going backwards)
+type State = (Location, Vec<i32>);
+
+// The fewest steps between each state and the start or the hole, and the move
+// linking it to the state it was reached from, or the state it leads on to
+type Reached = HashMap<State, (i32, Option<(State, Card, Direction, MoveOutcome)>)>;
+
+// The positions each move can reach the hole from, and the cards it uses
+type WaysToHole = HashMap<Location, Vec<Vec<i32>>>;
+
+// Every way to reach the hole using `depth` cards or fewer, worked backwards
+// from the hole. Gives up as soon as keep_going says so
+fn regress_from_hole(
+    map: &HashMap<Location, Tile>,
+    cards: &[Card],
+    card_counts: &[i32],
+    transitions: &KnownMoves,
+    depth: i32,
+    keep_going: &mut impl FnMut() -> bool,
+) -> Option<(Reached, WaysToHole)> {
+    let mut moves_into: HashMap<Location, Vec<(Location, usize, Direction, MoveOutcome)>> =
+        HashMap::new();
+    for start in sorted_locations(map) {
+        // Nothing moves on once it's in the hole
+        if map[&start].terrain == Terrain::Hole {
+            continue;
+        }
+        for (i, card) in cards.iter().enumerate() {
+            for direction in DIRECTIONS.iter() {
+                if let Some(Ok(outcome)) = transitions.get(&(start, *card, *direction)) {
+                    moves_into
+                        .entry(outcome.position)
+                        .or_default()
+                        .push((start, i, *direction, *outcome));
+                }
+            }
+        }
+    }
+
+    let mut reached: Reached = HashMap::new();
+    let mut ways_to_hole: WaysToHole = HashMap::new();
+    let mut layer = Vec::new();
+    for hole in sorted_locations(map) {
+        if map[&hole].terrain == Terrain::Hole {
+            let state = (hole, vec![0; cards.len()]);
+            reached.insert(state.clone(), (0, None));
+            ways_to_hole.entry(hole).or_default().push(state.1.clone());
+            layer.push(state);
+        }
+    }
+    for _ in 0..depth {
+        let mut previous_layer = Vec::new();
+        for state in &layer {
+            if !keep_going() {
+                return None;
+            }
+            let steps_from_end = reached[state].0;
+            for (start, i, direction, outcome) in moves_into.get(&state.0).into_iter().flatten() {
+                if state.1[*i] == card_counts[*i] {
+                    continue;
+                }
+                let mut used = state.1.clone();
+                used[*i] += 1;
+                let steps = outcome.steps + steps_from_end;
+                let previous = (*start, used);
+                let fewest = reached.get(&previous).map(|(fewest, _)| *fewest);
+                if fewest.is_some_and(|fewest| fewest <= steps) {
+                    continue;
+                }
+                if fewest.is_none() {
+                    ways_to_hole
+                        .entry(*start)
+                        .or_default()
+                        .push(previous.1.clone());
+                    previous_layer.push(previous.clone());
+                }
+                let next_move = (state.clone(), cards[*i], *direction, *outcome);
+                reached.insert(previous, (steps, Some(next_move)));
+            }
+        }
+        layer = previous_layer;
+    }
+    Some((reached, ways_to_hole))
+}
+
+// The solution with the fewest steps, found by searching forwards from the
+// start with half of the hand and backwards from the hole with the other
+// half, then joining the two halves where they meet. keep_going is asked
+// before each state is expanded, and stops the search when it says no
+pub fn fewest_steps(
+    map: &HashMap<Location, Tile>,
+    start: Location,
+    cards: &[Card],
+    card_counts: &[i32],
+    transitions: &KnownMoves,
+    keep_going: &mut impl FnMut() -> bool,
+) -> Option<Solution> {
+    let hand_size: i32 = card_counts.iter().sum();
+    let backward_depth = (hand_size + 1) / 2;
+    let (backward, ways_to_hole) = regress_from_hole(
+        map,
+        cards,
+        card_counts,
+        transitions,
+        backward_depth,
+        keep_going,
+    )?;
+
+    let mut forward: Reached = HashMap::new();
+    forward.insert((start, card_counts.to_vec()), (0, None));
+    let mut layer = vec![(start, card_counts.to_vec())];
+    // The fewest steps so far, and the states either side of where the halves meet
+    let mut best: Option<(i32, State, State)> = None;
+    for depth in 0..=(hand_size - backward_depth) {
+        let mut next_layer = Vec::new();
+        for state in &layer {
+            if !keep_going() {
+                return None;
+            }
+            let (position, remaining) = state;
+            let steps_so_far = forward[state].0;
+            // Meet any backward path that only needs cards that are left
+            for used in ways_to_hole.get(position).into_iter().flatten() {
+                if used.iter().zip(remaining).all(|(u, r)| u <= r) {
+                    let meeting = (*position, used.clone());
+                    let steps = steps_so_far + backward[&meeting].0;
+                    if best.as_ref().is_none_or(|(fewest, _, _)| steps < *fewest) {
+                        best = Some((steps, state.clone(), meeting));
+                    }
+                }
+            }
+            if depth == hand_size - backward_depth || map[position].terrain == Terrain::Hole {
+                continue;
+            }
+            for (i, card) in cards.iter().enumerate() {
+                if remaining[i] == 0 {
+                    continue;
+                }
+                for direction in DIRECTIONS.iter() {
+                    if let Some(Ok(outcome)) = transitions.get(&(*position, *card, *direction)) {
+                        let mut remaining = remaining.clone();
+                        remaining[i] -= 1;
+                        let steps = steps_so_far + outcome.steps;
+                        let next = (outcome.position, remaining);
+                        let fewest = forward.get(&next).map(|(fewest, _)| *fewest);
+                        if fewest.is_some_and(|fewest| fewest <= steps) {
+                            continue;
+                        }
+                        if fewest.is_none() {
+                            next_layer.push(next.clone());
+                        }
+                        let previous_move = (state.clone(), *card, *direction, *outcome);
+                        forward.insert(next, (steps, Some(previous_move)));
+                    }
+                }
+            }
+        }
+        layer = next_layer;
+    }
+
+    let (_, mut state, mut meeting) = best?;
+    let mut solution = Vec::new();
+    while let Some((previous, card, direction, outcome)) = &forward[&state].1 {
+        solution.push((*card, *direction, *outcome));
+        state = previous.clone();
+    }
+    solution.reverse();
+    while let Some((next, card, direction, outcome)) = &backward[&meeting].1 {
+        solution.push((*card, *direction, *outcome));
+        meeting = next.clone();
+    }
+    Some(solution)
+}
@@ -0,0 +1,228 @@
+use crate::transitions::{all_transitions, sorted_locations};
+use crate::{count_cards, read_level_file, Card, KnownMoves, Location, Terrain, Tile, DIRECTIONS};
+use std::collections::HashMap;
+
+// Whether the hole can still be reached from each tile, with each selection
+// of cards from the hand. A selection is stored as a number in a mixed radix,
+// with a digit per card, so taking a card away always gives a smaller number
+pub struct DeadEnds {
+    locations: Vec<Location>,
+    index_of: HashMap<Location, usize>,
+    hand: Vec<i32>,
+    place_values: Vec<usize>,
+    // Indexed by selection, then location
+    winnable: Vec<Vec<bool>>,
+}
+
+impl DeadEnds {
+    pub fn analyse(
+        map: &HashMap<Location, Tile>,
+        cards: &[Card],
+        card_counts: &[i32],
+        transitions: &KnownMoves,
+    ) -> DeadEnds {
+        DeadEnds::analyse_while(map, cards, card_counts, transitions, &mut || true).unwrap()
+    }
+
+    // The same analysis, giving up if keep_going says no before a selection is filled in
+    pub fn analyse_while(
+        map: &HashMap<Location, Tile>,
+        cards: &[Card],
+        card_counts: &[i32],
+        transitions: &KnownMoves,
+        keep_going: &mut impl FnMut() -> bool,
+    ) -> Option<DeadEnds> {
+        let locations = sorted_locations(map);
+        let index_of: HashMap<Location, usize> =
+            locations.iter().enumerate().map(|(i, l)| (*l, i)).collect();
+        let mut place_values = Vec::new();
+        let mut selections = 1;
+        for count in card_counts {
+            place_values.push(selections);
+            selections *= *count as usize + 1;
+        }
+        let in_hole: Vec<bool> = locations
+            .iter()
+            .map(|l| map.get(l).unwrap().terrain == Terrain::Hole)
+            .collect();
+
+        // Where each card goes from each tile, so the table is quick to fill in
+        let destinations: Vec<Vec<Vec<usize>>> = locations
+            .iter()
+            .map(|location| {
+                cards
+                    .iter()
+                    .map(|card| {
+                        DIRECTIONS
+                            .iter()
+                            .filter_map(|direction| {
+                                match transitions.get(&(*location, *card, *direction)) {
+                                    Some(Ok(outcome)) => Some(index_of[&outcome.position]),
+                                    _ => None,
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut winnable: Vec<Vec<bool>> = Vec::with_capacity(selections);
+        for selection in 0..selections {
+            if !keep_going() {
+                return None;
+            }
+            let mut from_here = in_hole.clone();
+            for (l, can_win) in from_here.iter_mut().enumerate() {
+                if *can_win {
+                    continue;
+                }
+                *can_win = (0..cards.len()).any(|i| {
+                    let count = selection / place_values[i] % (card_counts[i] as usize + 1);
+                    count > 0
+                        && destinations[l][i]
+                            .iter()
+                            .any(|&end| in_hole[end] || winnable[selection - place_values[i]][end])
+                });
+            }
+            winnable.push(from_here);
+        }
+
+        Some(DeadEnds {
+            locations,
+            index_of,
+            hand: card_counts.to_vec(),
+            place_values,
+            winnable,
+        })
+    }
+
+    fn selection_of(&self, card_counts: &[i32]) -> usize {
+        card_counts
+            .iter()
+            .zip(&self.place_values)
+            .map(|(count, place_value)| *count as usize * place_value)
+            .sum()
+    }
+
+    fn counts_of(&self, selection: usize) -> Vec<i32> {
+        self.place_values
+            .iter()
+            .zip(&self.hand)
+            .map(|(place_value, count)| (selection / place_value % (*count as usize + 1)) as i32)
+            .collect()
+    }
+
+    pub fn is_winnable(&self, location: Location, card_counts: &[i32]) -> bool {
+        match self.index_of.get(&location) {
+            Some(&l) => self.winnable[self.selection_of(card_counts)][l],
+            None => false,
+        }
+    }
+
+    // The largest hands that can't reach the hole from a tile, leaving out the
+    // empty hand. Any smaller hand can't reach it either
+    pub fn largest_losing_hands(&self, location: Location) -> Vec<Vec<i32>> {
+        let l = self.index_of[&location];
+        (1..self.winnable.len())
+            .filter(|&selection| !self.winnable[selection][l])
+            .filter(|&selection| {
+                let counts = self.counts_of(selection);
+                (0..counts.len()).all(|i| {
+                    counts[i] == self.hand[i] || self.winnable[selection + self.place_values[i]][l]
+                })
+            })
+            .map(|selection| self.counts_of(selection))
+            .collect()
+    }
+
+    pub fn hand(&self) -> &[i32] {
+        &self.hand
+    }
+
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+}
+
+fn describe_hand(cards: &[Card], card_counts: &[i32]) -> String {
+    let mut hand = Vec::new();
+    for (card, count) in cards.iter().zip(card_counts) {
+        for _ in 0..*count {
+            hand.push(format!("{}/{}", card.airborne, card.rolling));
+        }
+    }
+    hand.join(" ")
+}
+
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("Usage: golf-peaks deadends <level>");
+        std::process::exit(2);
+    }
+    let (map, all_cards, _) = read_level_file(&args[0]);
+    let (unique_cards, card_count) = count_cards(&all_cards);
+    let dead_ends = DeadEnds::analyse(
+        &map,
+        &unique_cards,
+        &card_count,
+        &all_transitions(&map, &unique_cards),
+    );
+    for location in dead_ends.locations() {
+        if map.get(location).unwrap().terrain == Terrain::Hole {
+            continue;
+        }
+        if !dead_ends.is_winnable(*location, &card_count) {
+            println!(
+                "{},{}: can't reach the hole with any cards",
+                location.x, location.y
+            );
+            continue;
+        }
+        for hand in dead_ends.largest_losing_hands(*location) {
+            println!(
+                "{},{}: can't reach the hole with {}",
+                location.x,
+                location.y,
+                describe_hand(&unique_cards, &hand)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_deadends {
+    use super::*;
+
+    fn level() -> (HashMap<Location, Tile>, Vec<Card>, Vec<i32>) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Trap, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        (map, vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 1, rolling: 0 }], vec![2, 1])
+    }
+
+    #[test]
+    fn only_a_chip_gets_out_of_a_trap() {
+        let (map, cards, counts) = level();
+
+        let dead_ends = DeadEnds::analyse(&map, &cards, &counts, &all_transitions(&map, &cards));
+
+        assert!(dead_ends.is_winnable(Location { x: 0, y: 0 }, &[2, 1]));
+        assert!(dead_ends.is_winnable(Location { x: 0, y: 0 }, &[1, 1]));
+        assert!(!dead_ends.is_winnable(Location { x: 0, y: 0 }, &[2, 0]));
+        assert!(dead_ends.is_winnable(Location { x: 1, y: 0 }, &[1, 0]));
+        assert!(!dead_ends.is_winnable(Location { x: 1, y: 0 }, &[0, 0]));
+    }
+
+    #[test]
+    fn lists_largest_losing_hands() {
+        let (map, cards, counts) = level();
+
+        let dead_ends = DeadEnds::analyse(&map, &cards, &counts, &all_transitions(&map, &cards));
+
+        assert_eq!(dead_ends.largest_losing_hands(Location { x: 0, y: 0 }), vec![vec![2, 0], vec![0, 1]]);
+        assert_eq!(dead_ends.largest_losing_hands(Location { x: 1, y: 0 }), Vec::<Vec<i32>>::new());
+    }
+}
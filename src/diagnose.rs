@@ -0,0 +1,194 @@
+use crate::distances::steps_to_hole;
+use crate::transitions::all_transitions;
+use crate::{
+    describe_move, Card, Direction, Location, MoveFailure, MoveOutcome, Terrain, Tile, DIRECTIONS,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// How close the ball got to the hole, either in steps if every card could be
+// reused, or in tiles if the hole can't be reached from there at all
+#[derive(PartialEq, Debug)]
+pub enum Distance {
+    Steps(i32),
+    Tiles(i32),
+}
+
+// A position and hand, with the state and move that first reached it
+type State = (
+    Location,
+    Vec<i32>,
+    Option<(usize, Card, Direction, MoveOutcome)>,
+);
+
+#[derive(PartialEq, Debug)]
+pub struct Diagnosis {
+    pub closest: Location,
+    pub distance: Option<Distance>,
+    pub moves_to_closest: Vec<(Card, Direction, MoveOutcome)>,
+    pub unusable_cards: Vec<Card>,
+    // Moves that sank in quicksand or hit the loop guard, and where they started
+    pub failed_moves: Vec<(Location, Card, Direction, MoveFailure)>,
+}
+
+// Plays every card in every order, to explain why none of them reach the hole
+pub fn diagnose(
+    map: &HashMap<Location, Tile>,
+    start: Location,
+    cards: &[Card],
+    card_counts: &[i32],
+) -> Diagnosis {
+    let transitions = all_transitions(map, cards);
+    let relaxed = steps_to_hole(map, &transitions);
+    let holes: Vec<Location> = map
+        .iter()
+        .filter(|(_, tile)| tile.terrain == Terrain::Hole)
+        .map(|(location, _)| *location)
+        .collect();
+    let distance_of = |location: &Location| match relaxed.get(location) {
+        Some(steps) => Some(Distance::Steps(*steps)),
+        None => holes
+            .iter()
+            .map(|hole| (hole.x - location.x).abs() + (hole.y - location.y).abs())
+            .min()
+            .map(Distance::Tiles),
+    };
+    // Any number of steps is closer than any number of tiles
+    let rank = |distance: &Option<Distance>| match distance {
+        Some(Distance::Steps(steps)) => (0, *steps),
+        Some(Distance::Tiles(tiles)) => (1, *tiles),
+        None => (2, 0),
+    };
+
+    // Breadth first, so each position is first reached with the fewest moves
+    let mut states: Vec<State> = vec![(start, card_counts.to_vec(), None)];
+    let mut seen: HashSet<(Location, Vec<i32>)> = HashSet::new();
+    seen.insert((start, card_counts.to_vec()));
+    let mut queue = VecDeque::from(vec![0]);
+    let mut closest = 0;
+    let mut closest_distance = distance_of(&start);
+    let mut usable = vec![false; cards.len()];
+    let mut failed_moves = Vec::new();
+    while let Some(s) = queue.pop_front() {
+        let (position, remaining) = (states[s].0, states[s].1.clone());
+        for (i, card) in cards.iter().enumerate() {
+            if remaining[i] == 0 {
+                continue;
+            }
+            for direction in DIRECTIONS.iter() {
+                match transitions[&(position, *card, *direction)] {
+                    Ok(outcome) => {
+                        usable[i] = true;
+                        let mut remaining = remaining.clone();
+                        remaining[i] -= 1;
+                        if seen.insert((outcome.position, remaining.clone())) {
+                            let distance = distance_of(&outcome.position);
+                            if rank(&distance) < rank(&closest_distance) {
+                                closest = states.len();
+                                closest_distance = distance;
+                            }
+                            states.push((
+                                outcome.position,
+                                remaining,
+                                Some((s, *card, *direction, outcome)),
+                            ));
+                            queue.push_back(states.len() - 1);
+                        }
+                    }
+                    Err(failure @ MoveFailure::SankInQuicksand(_))
+                    | Err(failure @ MoveFailure::StuckInLoop(_)) => {
+                        let failed_move = (position, *card, *direction, failure);
+                        if !failed_moves.contains(&failed_move) {
+                            failed_moves.push(failed_move);
+                        }
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    let mut moves_to_closest = Vec::new();
+    let mut s = closest;
+    while let Some((previous, card, direction, outcome)) = states[s].2 {
+        moves_to_closest.push((card, direction, outcome));
+        s = previous;
+    }
+    moves_to_closest.reverse();
+
+    Diagnosis {
+        closest: states[closest].0,
+        distance: closest_distance,
+        moves_to_closest,
+        unusable_cards: cards
+            .iter()
+            .zip(&usable)
+            .filter(|(_, usable)| !**usable)
+            .map(|(card, _)| *card)
+            .collect(),
+        failed_moves,
+    }
+}
+
+pub fn report(diagnosis: &Diagnosis) -> String {
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "Closest approach: {},{}{}",
+        diagnosis.closest.x,
+        diagnosis.closest.y,
+        match diagnosis.distance {
+            Some(Distance::Steps(steps)) =>
+                format!(", {} steps from the hole if cards could be reused", steps),
+            Some(Distance::Tiles(tiles)) => format!(", {} tiles from the hole", tiles),
+            None => String::new(),
+        }
+    ));
+    for (card, direction, _) in &diagnosis.moves_to_closest {
+        lines.push(format!("  {}", describe_move(card, direction)));
+    }
+    if !diagnosis.unusable_cards.is_empty() {
+        let cards: Vec<String> = diagnosis
+            .unusable_cards
+            .iter()
+            .map(|card| format!("{}/{}", card.airborne, card.rolling))
+            .collect();
+        lines.push(format!(
+            "Never playable from anywhere reached: {}",
+            cards.join(" ")
+        ));
+    }
+    for (position, card, direction, failure) in &diagnosis.failed_moves {
+        lines.push(format!(
+            "{} from {},{}: {}",
+            describe_move(card, direction),
+            position.x,
+            position.y,
+            failure
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_diagnose {
+    use super::*;
+
+    #[test]
+    fn explains_why_the_hole_is_out_of_reach() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 0, y: 1 }, Tile { terrain: Terrain::Quicksand, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        let cards = vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 5 }];
+
+        let diagnosis = diagnose(&map, Location { x: 0, y: 0 }, &cards, &[1, 1]);
+
+        assert_eq!(diagnosis.closest, Location { x: 1, y: 0 });
+        assert_eq!(diagnosis.distance, Some(Distance::Tiles(2)));
+        assert_eq!(diagnosis.moves_to_closest.len(), 1);
+        assert_eq!((diagnosis.moves_to_closest[0].0, diagnosis.moves_to_closest[0].1), (cards[0], Direction::East));
+        assert_eq!(diagnosis.unusable_cards, vec![Card { airborne: 0, rolling: 5 }]);
+        assert_eq!(diagnosis.failed_moves, vec![(Location { x: 0, y: 0 }, cards[0], Direction::North, MoveFailure::SankInQuicksand(Location { x: 0, y: 1 }))]);
+    }
+}
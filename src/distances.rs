@@ -0,0 +1,140 @@
+use crate::transitions::{all_transitions, sorted_locations};
+use crate::{count_cards, read_level_file, KnownMoves, Location, Terrain, Tile};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+// Each tile's moves that end on a given tile, and the steps they take
+fn moves_into(transitions: &KnownMoves) -> HashMap<Location, Vec<(Location, i32)>> {
+    let mut moves_into: HashMap<Location, Vec<(Location, i32)>> = HashMap::new();
+    for ((start, _, _), move_result) in transitions {
+        if let Ok(outcome) = move_result {
+            moves_into
+                .entry(outcome.position)
+                .or_default()
+                .push((*start, outcome.steps));
+        }
+    }
+    moves_into
+}
+
+fn holes(map: &HashMap<Location, Tile>) -> Vec<Location> {
+    sorted_locations(map)
+        .into_iter()
+        .filter(|l| map.get(l).unwrap().terrain == Terrain::Hole)
+        .collect()
+}
+
+// The fewest steps from each tile to the hole if every card in the hand could
+// be played as many times as needed. A real hand can never do better, so this
+// is a lower bound on the steps left from any point in the search. Tiles that
+// can't reach the hole at all are left out
+pub fn steps_to_hole(
+    map: &HashMap<Location, Tile>,
+    transitions: &KnownMoves,
+) -> HashMap<Location, i32> {
+    let moves_into = moves_into(transitions);
+
+    // Dijkstra's algorithm, working backwards from the hole
+    let mut distances: HashMap<Location, i32> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    for hole in holes(map) {
+        queue.push(Reverse((0, hole.x, hole.y)));
+    }
+    while let Some(Reverse((steps, x, y))) = queue.pop() {
+        let location = Location { x, y };
+        if distances.contains_key(&location) {
+            continue;
+        }
+        distances.insert(location, steps);
+        for (start, move_steps) in moves_into.get(&location).into_iter().flatten() {
+            if !distances.contains_key(start) {
+                queue.push(Reverse((steps + move_steps, start.x, start.y)));
+            }
+        }
+    }
+    distances
+}
+
+// The fewest cards from each tile to the hole, if every card could be played
+// as many times as needed
+pub fn strokes_to_hole(
+    map: &HashMap<Location, Tile>,
+    transitions: &KnownMoves,
+) -> HashMap<Location, i32> {
+    let moves_into = moves_into(transitions);
+
+    // Breadth first search, working backwards from the hole
+    let mut distances: HashMap<Location, i32> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for hole in holes(map) {
+        distances.insert(hole, 0);
+        queue.push_back(hole);
+    }
+    while let Some(location) = queue.pop_front() {
+        let strokes = distances[&location];
+        for (start, _) in moves_into.get(&location).into_iter().flatten() {
+            if !distances.contains_key(start) {
+                distances.insert(*start, strokes + 1);
+                queue.push_back(*start);
+            }
+        }
+    }
+    distances
+}
+
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("Usage: golf-peaks distances <level>");
+        std::process::exit(2);
+    }
+    let (map, all_cards, _) = read_level_file(&args[0]);
+    let (unique_cards, _) = count_cards(&all_cards);
+    let transitions = all_transitions(&map, &unique_cards);
+    let strokes = strokes_to_hole(&map, &transitions);
+    let steps = steps_to_hole(&map, &transitions);
+    println!("x,y,strokes,steps");
+    for location in sorted_locations(&map) {
+        let show = |distance: Option<&i32>| distance.map(|d| d.to_string()).unwrap_or_default();
+        println!(
+            "{},{},{},{}",
+            location.x,
+            location.y,
+            show(strokes.get(&location)),
+            show(steps.get(&location))
+        );
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_distances {
+    use super::*;
+    use crate::transitions::all_transitions;
+    use crate::Card;
+
+    #[test]
+    fn counts_strokes_and_steps_back_from_the_hole() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        map.insert(Location { x: 4, y: 0 }, Tile { terrain: Terrain::Trap, elevation: 0, corner: None });
+        let cards = vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 2 }];
+
+        let distances = steps_to_hole(&map, &all_transitions(&map, &cards));
+
+        assert_eq!(distances.get(&Location { x: 3, y: 0 }), Some(&0));
+        assert_eq!(distances.get(&Location { x: 2, y: 0 }), Some(&4));
+        assert_eq!(distances.get(&Location { x: 1, y: 0 }), Some(&5));
+        assert_eq!(distances.get(&Location { x: 0, y: 0 }), Some(&9));
+        // Only a chip can get out of a trap
+        assert_eq!(distances.get(&Location { x: 4, y: 0 }), None);
+
+        let distances = strokes_to_hole(&map, &all_transitions(&map, &cards));
+
+        assert_eq!(distances.get(&Location { x: 1, y: 0 }), Some(&1));
+        assert_eq!(distances.get(&Location { x: 0, y: 0 }), Some(&2));
+        assert_eq!(distances.get(&Location { x: 4, y: 0 }), None);
+    }
+}
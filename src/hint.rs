@@ -0,0 +1,168 @@
+use crate::solver::{self, Objective};
+use crate::verify::{describe_error, parse_move, replay};
+use crate::{
+    count_cards, describe_move, read_level_file, value_of_flag, Card, Direction, Location, Tile,
+};
+use std::collections::HashMap;
+
+// What to tell the player, given the moves they've made so far
+#[derive(PartialEq, Debug)]
+pub enum Hint {
+    InHole,
+    // The next moves of a best solution from here
+    Play(Vec<(Card, Direction)>),
+    Unreachable,
+}
+
+// Reads moves like "0/2 right,0/1 up"
+pub fn parse_played(text: &str) -> Result<Vec<(Card, Direction)>, String> {
+    text.split(',')
+        .filter(|m| !m.trim().is_empty())
+        .map(parse_move)
+        .collect()
+}
+
+// Plays the moves so far, then looks for the best way on from there. Any
+// mistake in the moves means there's nothing to give a hint for
+pub fn hint(
+    map: &HashMap<Location, Tile>,
+    all_cards: &[Card],
+    starting_position: Location,
+    played: &[(Card, Direction)],
+    depth: usize,
+) -> Result<Hint, String> {
+    let state = replay(map, all_cards, starting_position, played);
+    if let Some(message) = describe_error(played, &state) {
+        return Err(message);
+    }
+    if state.in_hole(map) {
+        return Ok(Hint::InHole);
+    }
+    let (unique_cards, card_count) = count_cards(&state.remaining_cards);
+    let solution = solver::solve(
+        map,
+        state.position,
+        &unique_cards,
+        &card_count,
+        Objective::Steps,
+    );
+    Ok(match solution {
+        Some(solution) => Hint::Play(
+            solution
+                .iter()
+                .take(depth)
+                .map(|(card, direction, _)| (*card, *direction))
+                .collect(),
+        ),
+        None => Hint::Unreachable,
+    })
+}
+
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("Usage: golf-peaks hint <level> [--played \"0/2 right,0/1 up\"] [--depth N]");
+        std::process::exit(2);
+    }
+    let (map, all_cards, starting_position) = read_level_file(&args[0]);
+    let played = match parse_played(&value_of_flag("--played").unwrap_or_default()) {
+        Ok(played) => played,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    let depth = match value_of_flag("--depth").map(|depth| depth.parse::<usize>()) {
+        None => 1,
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            eprintln!("Expected a number of moves for --depth");
+            std::process::exit(2);
+        }
+    };
+
+    match hint(&map, &all_cards, starting_position, &played, depth) {
+        Ok(Hint::InHole) => println!("The ball is already in the hole"),
+        Ok(Hint::Play(moves)) => {
+            for (card, direction) in moves {
+                println!("{}", describe_move(&card, &direction));
+            }
+        }
+        Ok(Hint::Unreachable) => {
+            println!("The hole can no longer be reached from here");
+            std::process::exit(1);
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_hint {
+    use super::*;
+    use crate::Terrain;
+
+    fn level() -> (HashMap<Location, Tile>, Vec<Card>, Location) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        (map, vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 2 }], Location { x: 0, y: 0 })
+    }
+
+    #[test]
+    fn reads_comma_separated_moves() {
+        let played = parse_played("0/2 right, Use 1/0 up,").unwrap();
+
+        assert_eq!(played, vec![
+            (Card { airborne: 0, rolling: 2 }, Direction::East),
+            (Card { airborne: 1, rolling: 0 }, Direction::North),
+        ]);
+        assert_eq!(parse_played("").unwrap(), vec![]);
+        assert!(parse_played("0/2 right,sideways").is_err());
+    }
+
+    #[test]
+    fn gives_the_next_move_after_those_played() {
+        let (map, cards, start) = level();
+
+        let next = hint(&map, &cards, start, &parse_played("0/2 right").unwrap(), 1);
+
+        assert_eq!(next, Ok(Hint::Play(vec![(Card { airborne: 0, rolling: 1 }, Direction::East)])));
+    }
+
+    #[test]
+    fn depth_gives_the_moves_that_follow() {
+        let (map, cards, start) = level();
+
+        let one = hint(&map, &cards, start, &[], 1).unwrap();
+        let two = hint(&map, &cards, start, &[], 2).unwrap();
+        let more = hint(&map, &cards, start, &[], 5).unwrap();
+
+        match (one, two) {
+            (Hint::Play(one), Hint::Play(two)) => {
+                assert_eq!(one.len(), 1);
+                assert_eq!(two.len(), 2);
+                assert_eq!(one[0], two[0]);
+            }
+            other => panic!("Expected moves to play, got {:?}", other),
+        }
+        assert_eq!(more, hint(&map, &cards, start, &[], 2).unwrap());
+    }
+
+    #[test]
+    fn says_when_the_hole_is_out_of_reach() {
+        let (map, cards, start) = level();
+
+        let stuck = hint(&map, &cards, start, &parse_played("0/2 right,0/1 left").unwrap(), 1);
+        let done = hint(&map, &cards, start, &parse_played("0/2 right,0/1 right").unwrap(), 1);
+        let mistake = hint(&map, &cards, start, &parse_played("0/2 left").unwrap(), 1);
+
+        assert_eq!(stuck, Ok(Hint::Unreachable));
+        assert_eq!(done, Ok(Hint::InHole));
+        assert!(mistake.unwrap_err().starts_with("Use 0/2 left: "));
+    }
+}
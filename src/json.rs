@@ -0,0 +1,292 @@
+use std::fmt;
+
+// Just enough JSON to read and write the solver's own output formats
+#[derive(PartialEq, Clone, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Json::Number(n) if n.fract() == 0.0 => Some(*n as i32),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Json {
+        Json::Number(n)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(String::from(s))
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+pub fn parse(text: &str) -> Result<Json, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let value = parse_value(&chars, &mut i)?;
+    skip_whitespace(&chars, &mut i);
+    if i < chars.len() {
+        return Err(format!("Unexpected trailing characters at {}", i));
+    }
+    Ok(value)
+}
+
+fn skip_whitespace(chars: &[char], i: &mut usize) {
+    while *i < chars.len() && chars[*i].is_whitespace() {
+        *i += 1;
+    }
+}
+
+fn expect(chars: &[char], i: &mut usize, c: char) -> Result<(), String> {
+    skip_whitespace(chars, i);
+    if chars.get(*i) == Some(&c) {
+        *i += 1;
+        Ok(())
+    } else {
+        Err(format!("Expected '{}' at {}", c, i))
+    }
+}
+
+fn parse_value(chars: &[char], i: &mut usize) -> Result<Json, String> {
+    skip_whitespace(chars, i);
+    match chars.get(*i) {
+        Some('{') => {
+            *i += 1;
+            let mut fields = Vec::new();
+            skip_whitespace(chars, i);
+            if chars.get(*i) == Some(&'}') {
+                *i += 1;
+                return Ok(Json::Object(fields));
+            }
+            loop {
+                skip_whitespace(chars, i);
+                let key = parse_string(chars, i)?;
+                expect(chars, i, ':')?;
+                fields.push((key, parse_value(chars, i)?));
+                skip_whitespace(chars, i);
+                match chars.get(*i) {
+                    Some(',') => *i += 1,
+                    Some('}') => {
+                        *i += 1;
+                        return Ok(Json::Object(fields));
+                    }
+                    _ => return Err(format!("Expected ',' or '}}' at {}", i)),
+                }
+            }
+        }
+        Some('[') => {
+            *i += 1;
+            let mut items = Vec::new();
+            skip_whitespace(chars, i);
+            if chars.get(*i) == Some(&']') {
+                *i += 1;
+                return Ok(Json::Array(items));
+            }
+            loop {
+                items.push(parse_value(chars, i)?);
+                skip_whitespace(chars, i);
+                match chars.get(*i) {
+                    Some(',') => *i += 1,
+                    Some(']') => {
+                        *i += 1;
+                        return Ok(Json::Array(items));
+                    }
+                    _ => return Err(format!("Expected ',' or ']' at {}", i)),
+                }
+            }
+        }
+        Some('"') => Ok(Json::String(parse_string(chars, i)?)),
+        Some('t') => parse_literal(chars, i, "true", Json::Bool(true)),
+        Some('f') => parse_literal(chars, i, "false", Json::Bool(false)),
+        Some('n') => parse_literal(chars, i, "null", Json::Null),
+        Some(_) => {
+            let start = *i;
+            while *i < chars.len() && "+-.0123456789eE".contains(chars[*i]) {
+                *i += 1;
+            }
+            let number: String = chars[start..*i].iter().collect();
+            number
+                .parse::<f64>()
+                .map(Json::Number)
+                .map_err(|_| format!("Invalid value at {}", start))
+        }
+        None => Err(String::from("Unexpected end of input")),
+    }
+}
+
+fn parse_literal(
+    chars: &[char],
+    i: &mut usize,
+    literal: &str,
+    value: Json,
+) -> Result<Json, String> {
+    let end = *i + literal.len();
+    if end <= chars.len() && chars[*i..end].iter().copied().eq(literal.chars()) {
+        *i = end;
+        Ok(value)
+    } else {
+        Err(format!("Invalid value at {}", i))
+    }
+}
+
+fn parse_string(chars: &[char], i: &mut usize) -> Result<String, String> {
+    if chars.get(*i) != Some(&'"') {
+        return Err(format!("Expected string at {}", i));
+    }
+    *i += 1;
+    let mut s = String::new();
+    while let Some(&c) = chars.get(*i) {
+        *i += 1;
+        match c {
+            '"' => return Ok(s),
+            '\\' => {
+                let escaped = chars.get(*i).copied();
+                *i += 1;
+                match escaped {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('u') => {
+                        let hex: String = chars.iter().skip(*i).take(4).collect();
+                        *i += 4;
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| format!("Invalid escape at {}", i))?;
+                        s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    }
+                    Some(c) => s.push(c),
+                    None => break,
+                }
+            }
+            c => s.push(c),
+        }
+    }
+    Err(String::from("Unterminated string"))
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_json {
+    use super::*;
+
+    #[test]
+    fn round_trips_nested_values() {
+        let text = r#"{"moves":[{"airborne":0,"rolling":2,"direction":"right"}],"solved":true,"note":null,"steps":-1.5}"#;
+
+        let value = parse(text).unwrap();
+
+        assert_eq!(value.to_string(), text);
+        assert_eq!(value.get("moves").unwrap().as_array().unwrap()[0].get("rolling").unwrap().as_i32(), Some(2));
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let value = Json::from("say \"hi\"\n");
+
+        assert_eq!(value.to_string(), r#""say \"hi\"\n""#);
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("[1] 2").is_err());
+    }
+}
@@ -0,0 +1,1737 @@
+use crate::astar;
+use crate::bidirectional;
+use crate::deadends::DeadEnds;
+use crate::distances;
+use crate::transitions::sorted_locations;
+use crate::{
+    try_move, Card, Direction, KnownMoves, Location, MoveFailure, MoveOutcome, Terrain, Tile,
+    DIRECTIONS,
+};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub type Solution = Vec<(Card, Direction, MoveOutcome)>;
+
+pub fn steps_of(solution: &[(Card, Direction, MoveOutcome)]) -> i32 {
+    solution.iter().map(|(_, _, outcome)| outcome.steps).sum()
+}
+
+// Where each copy of a card is in the hand, nearest first, and how many
+// presses of q or e it takes to turn the hand to it. The game starts at the
+// first card every time
+fn copies_of(hand: &[Card], card: Card) -> Vec<(usize, usize)> {
+    let mut copies: Vec<(usize, usize)> = hand
+        .iter()
+        .enumerate()
+        .filter(|(_, &c)| c == card)
+        .map(|(i, _)| (i, i.min(hand.len() - i)))
+        .collect();
+    copies.sort_by_key(|(_, presses)| *presses);
+    copies
+}
+
+// The copy of a card that's quickest to turn the hand to, and the presses it takes
+pub fn rotations_to(hand: &[Card], card: Card) -> (usize, usize) {
+    copies_of(hand, card)[0]
+}
+
+// Which copy of each card to take out of the hand to play a solution in the
+// fewest presses, and the presses for each. Copies play the same, but taking
+// the nearest one isn't always best, as it moves the cards that come after it
+pub fn rotation_plan(
+    hand: &[Card],
+    solution: &[(Card, Direction, MoveOutcome)],
+) -> Vec<(usize, usize)> {
+    fn plan_from(
+        hand: &mut Vec<Card>,
+        cards: &[Card],
+        plan: &mut Vec<(usize, usize)>,
+        presses_so_far: usize,
+        best: &mut (usize, Vec<(usize, usize)>),
+    ) {
+        if presses_so_far >= best.0 {
+            return;
+        }
+        let Some((&card, cards)) = cards.split_first() else {
+            *best = (presses_so_far, plan.clone());
+            return;
+        };
+        for (i, presses) in copies_of(hand, card) {
+            hand.remove(i);
+            plan.push((i, presses));
+            plan_from(hand, cards, plan, presses_so_far + presses, best);
+            plan.pop();
+            hand.insert(i, card);
+        }
+    }
+    let cards: Vec<Card> = solution.iter().map(|(card, _, _)| *card).collect();
+    let mut best = (usize::MAX, Vec::new());
+    plan_from(&mut hand.to_vec(), &cards, &mut Vec::new(), 0, &mut best);
+    best.1
+}
+
+// Presses needed to play a solution, as each card is taken out of the hand
+pub fn rotations_of(hand: &[Card], solution: &[(Card, Direction, MoveOutcome)]) -> usize {
+    rotation_plan(hand, solution)
+        .iter()
+        .map(|(_, presses)| presses)
+        .sum()
+}
+
+// What makes one solution better than another
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Objective {
+    // Fewest steps, however many cards it takes
+    Steps,
+    // Fewest cards, then fewest steps
+    Strokes,
+    // Fewest steps, then fewest key presses turning the hand to each card
+    Rotations,
+}
+
+impl Objective {
+    pub fn named(name: &str) -> Option<Objective> {
+        match name {
+            "steps" => Some(Objective::Steps),
+            "strokes" => Some(Objective::Strokes),
+            "rotations" => Some(Objective::Rotations),
+            _ => None,
+        }
+    }
+}
+
+// How to search for the fewest steps
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Strategy {
+    DepthFirst,
+    Bidirectional,
+    AStar,
+}
+
+impl Strategy {
+    pub fn named(name: &str) -> Option<Strategy> {
+        match name {
+            "depth-first" => Some(Strategy::DepthFirst),
+            "bidirectional" => Some(Strategy::Bidirectional),
+            "astar" => Some(Strategy::AStar),
+            _ => None,
+        }
+    }
+}
+
+// What is known about the best solution from a position with some cards left
+#[derive(Clone, Debug)]
+enum Transposition {
+    Solved(Option<Solution>),
+    // Nothing from here takes fewer steps than this
+    AtLeast(i32),
+}
+
+type TranspositionTable = HashMap<(Location, Vec<i32>), Transposition>;
+
+type ImprovementCallback<'a> = Box<dyn FnMut(&[(Card, Direction, MoveOutcome)]) + 'a>;
+
+// How far a search has got, passed to the progress callback
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    pub nodes: u64,
+    pub best_steps: Option<i32>,
+    pub depth: usize,
+}
+
+type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+// Set from another thread to stop a search, see Search::cancel_with
+pub type CancelToken = Arc<AtomicBool>;
+
+// Counters for --stats, only kept when asked for so normal runs don't pay for them
+#[derive(Clone, Debug)]
+pub struct SearchStats {
+    started: Instant,
+    try_move_calls: u64,
+    known_move_hits: u64,
+    step_prunes: u64,
+    bound_prunes: u64,
+    dead_end_prunes: u64,
+    cycle_prunes: u64,
+    max_depth: usize,
+    improvements: u64,
+    simulation_time: Duration,
+}
+
+// The tables used to prune the search. They take a pass over every tile to
+// work out, so they sit behind an Arc for threads solving the same level to share
+#[derive(Clone, Default)]
+struct Pruning {
+    // Every move from every tile, see transitions::all_transitions
+    transitions: Option<Arc<KnownMoves>>,
+    // Fewest steps from each tile to the hole, see distances::steps_to_hole
+    lower_bounds: Option<Arc<HashMap<Location, i32>>>,
+    dead_ends: Option<Arc<DeadEnds>>,
+}
+
+// Whether the cancel token has been set, or the deadline has passed
+fn interrupted(cancel: &Option<CancelToken>, deadline: Option<Instant>) -> bool {
+    cancel
+        .as_ref()
+        .is_some_and(|token| token.load(Ordering::Relaxed))
+        || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+pub struct Search<'a> {
+    map: &'a HashMap<Location, Tile>,
+    cards: &'a [Card],
+    known_moves: KnownMoves,
+    // Don't look for solutions using more cards than this
+    stroke_limit: Option<usize>,
+    // Fewest steps found by any thread, and the steps taken to get to this point
+    shared_bound: Option<&'a AtomicI32>,
+    steps_taken: i32,
+    transpositions: Option<TranspositionTable>,
+    pub transposition_hits: u64,
+    // Moves played to get to this point, and the best solution found so far
+    moves: Solution,
+    best: Option<Solution>,
+    on_improvement: Option<ImprovementCallback<'a>>,
+    // Give up after this long, or after searching this many positions
+    deadline: Option<Instant>,
+    max_nodes: Option<u64>,
+    // Positions searched, only counted when something reads them, see tracked
+    pub nodes: u64,
+    stopped: bool,
+    // Set from elsewhere to stop the search, such as another thread or a GUI
+    cancel: Option<CancelToken>,
+    progress_every: u64,
+    on_progress: Option<ProgressCallback<'a>>,
+    stats: Option<SearchStats>,
+    pruning: Pruning,
+    // Whether to use the lower bounds and dead ends, see with_pruning
+    prune: bool,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(map: &'a HashMap<Location, Tile>, cards: &'a [Card]) -> Search<'a> {
+        Search {
+            map,
+            cards,
+            known_moves: HashMap::new(),
+            stroke_limit: None,
+            shared_bound: None,
+            steps_taken: 0,
+            transpositions: None,
+            transposition_hits: 0,
+            moves: Vec::new(),
+            best: None,
+            on_improvement: None,
+            deadline: None,
+            max_nodes: None,
+            nodes: 0,
+            stopped: false,
+            cancel: None,
+            progress_every: 0,
+            on_progress: None,
+            stats: None,
+            pruning: Pruning::default(),
+            prune: false,
+        }
+    }
+
+    // Works out the lower bounds and dead ends before searching, and cuts the
+    // branches they rule out. Building them simulates every move from every
+    // tile first, which only pays off on levels that take a while to search
+    pub fn with_pruning(&mut self) {
+        self.prune = true;
+    }
+
+    // Simulates every move from every tile, and works out the lower bounds from them.
+    // Being cancelled or running out of time part way through stops the search
+    fn prepare_transitions(&mut self) {
+        if self.pruning.transitions.is_none() {
+            let started = Instant::now();
+            let mut calls = 0;
+            let mut transitions = std::mem::take(&mut self.known_moves);
+            for location in sorted_locations(self.map) {
+                if interrupted(&self.cancel, self.deadline) {
+                    self.known_moves = transitions;
+                    self.stopped = true;
+                    return;
+                }
+                for card in self.cards {
+                    for direction in DIRECTIONS.iter() {
+                        transitions
+                            .entry((location, *card, *direction))
+                            .or_insert_with(|| {
+                                calls += 1;
+                                try_move(self.map, location, *card, *direction)
+                            });
+                    }
+                }
+            }
+            if let Some(stats) = self.stats.as_mut() {
+                stats.try_move_calls += calls;
+                stats.simulation_time += started.elapsed();
+            }
+            self.pruning.lower_bounds =
+                Some(Arc::new(distances::steps_to_hole(self.map, &transitions)));
+            self.pruning.transitions = Some(Arc::new(transitions));
+        }
+    }
+
+    // Works out the lower bounds and dead ends used to prune the search
+    fn prepare_pruning(&mut self, card_counts: &[i32]) {
+        if !self.prune {
+            return;
+        }
+        self.prepare_transitions();
+        // Dead ends are worked out for a particular hand
+        if self.stopped
+            || self
+                .pruning
+                .dead_ends
+                .as_ref()
+                .is_some_and(|dead_ends| dead_ends.hand() == card_counts)
+        {
+            return;
+        }
+        let (cancel, deadline) = (&self.cancel, self.deadline);
+        match DeadEnds::analyse_while(
+            self.map,
+            self.cards,
+            card_counts,
+            self.pruning.transitions.as_ref().unwrap(),
+            &mut || !interrupted(cancel, deadline),
+        ) {
+            Some(dead_ends) => self.pruning.dead_ends = Some(Arc::new(dead_ends)),
+            None => self.stopped = true,
+        }
+    }
+
+    // Whether the hole can't be reached from here with the cards that are left
+    fn is_dead_end(&self, position: Location, card_counts: &[i32]) -> bool {
+        self.prune
+            && self
+                .pruning
+                .dead_ends
+                .as_ref()
+                .is_some_and(|dead_ends| !dead_ends.is_winnable(position, card_counts))
+    }
+
+    // Whether there's no way to reach the hole from here in fewer steps than are left
+    fn cannot_beat(&self, position: Location, remaining_steps: Option<i32>) -> bool {
+        if !self.prune {
+            return false;
+        }
+        match self
+            .pruning
+            .lower_bounds
+            .as_ref()
+            .map(|bounds| bounds.get(&position))
+        {
+            None => false,
+            Some(None) => true,
+            Some(Some(steps)) => remaining_steps.is_some_and(|remaining| *steps >= remaining),
+        }
+    }
+
+    // Whether anything reads the number of positions searched. Searches that
+    // nothing is watching skip counting them, see expand_node
+    fn tracked(&self) -> bool {
+        self.stats.is_some()
+            || self.max_nodes.is_some()
+            || self.deadline.is_some()
+            || self.on_progress.is_some()
+    }
+
+    // Each search counts from zero, even when the same Search is used again
+    fn start_counting(&mut self) {
+        self.nodes = 0;
+        self.stopped = false;
+        if self.stats.is_some() {
+            self.collect_stats();
+        }
+    }
+
+    pub fn collect_stats(&mut self) {
+        self.stats = Some(SearchStats {
+            started: Instant::now(),
+            try_move_calls: 0,
+            known_move_hits: 0,
+            step_prunes: 0,
+            bound_prunes: 0,
+            dead_end_prunes: 0,
+            cycle_prunes: 0,
+            max_depth: 0,
+            improvements: 0,
+            simulation_time: Duration::ZERO,
+        });
+    }
+
+    fn record<const TRACKED: bool>(&mut self, update: impl FnOnce(&mut SearchStats)) {
+        if TRACKED {
+            if let Some(stats) = self.stats.as_mut() {
+                update(stats);
+            }
+        }
+    }
+
+    pub fn report_stats(&self) -> Option<String> {
+        let stats = self.stats.as_ref()?;
+        let lookups = stats.try_move_calls + stats.known_move_hits;
+        let elapsed = stats.started.elapsed();
+        let mut report = vec![
+            format!("Nodes expanded: {}", self.nodes),
+            format!("try_move calls: {}", stats.try_move_calls),
+            format!(
+                "known_moves hit rate: {:.1}% ({} of {})",
+                if lookups > 0 {
+                    100.0 * stats.known_move_hits as f64 / lookups as f64
+                } else {
+                    0.0
+                },
+                stats.known_move_hits,
+                lookups
+            ),
+            format!("Step count prunes: {}", stats.step_prunes),
+            format!("Lower bound prunes: {}", stats.bound_prunes),
+            format!("Dead end prunes: {}", stats.dead_end_prunes),
+            format!("Cycle prunes: {}", stats.cycle_prunes),
+            format!("Deepest position searched: {} moves in", stats.max_depth),
+            format!("Improving solutions: {}", stats.improvements),
+            format!(
+                "Time simulating: {:.3}s",
+                stats.simulation_time.as_secs_f64()
+            ),
+            format!(
+                "Time searching: {:.3}s",
+                elapsed.saturating_sub(stats.simulation_time).as_secs_f64()
+            ),
+        ];
+        if self.transpositions.is_some() {
+            report.push(format!(
+                "Transposition table hits: {}",
+                self.transposition_hits
+            ));
+        }
+        Some(report.join("\n"))
+    }
+
+    pub fn stop_after(&mut self, timeout: Option<Duration>, max_nodes: Option<u64>) {
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.max_nodes = max_nodes;
+    }
+
+    // Whether the search ran out of time or nodes, so its solution might not be the best
+    pub fn stopped_early(&self) -> bool {
+        self.stopped
+    }
+
+    // Called with each solution that beats the last one, as soon as it is found
+    pub fn on_improvement(&mut self, callback: impl FnMut(&[(Card, Direction, MoveOutcome)]) + 'a) {
+        self.on_improvement = Some(Box::new(callback));
+    }
+
+    // Stops the search as soon as the token is set, keeping the best solution so far
+    pub fn cancel_with(&mut self, token: CancelToken) {
+        self.cancel = Some(token);
+    }
+
+    // Called every so many positions searched, to show how the search is going
+    pub fn on_progress(&mut self, every: u64, callback: impl FnMut(&Progress) + 'a) {
+        self.progress_every = every.max(1);
+        self.on_progress = Some(Box::new(callback));
+    }
+
+    // Counts a position as searched, and checks whether there's any budget left for it.
+    // Untracked searches only check the cancel token
+    fn expand_node<const TRACKED: bool>(&mut self) -> bool {
+        if interrupted(&self.cancel, None) {
+            self.stopped = true;
+        }
+        if !TRACKED {
+            return !self.stopped;
+        }
+        self.nodes += 1;
+        if self
+            .max_nodes
+            .is_some_and(|max_nodes| self.nodes > max_nodes)
+            || (self.nodes.is_multiple_of(1024)
+                && self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline))
+        {
+            self.stopped = true;
+        }
+        if !self.stopped
+            && self.on_progress.is_some()
+            && self.nodes.is_multiple_of(self.progress_every)
+        {
+            let progress = Progress {
+                nodes: self.nodes,
+                best_steps: self.best.as_ref().map(|best| steps_of(best)),
+                depth: self.moves.len(),
+            };
+            if let Some(callback) = self.on_progress.as_mut() {
+                callback(&progress);
+            }
+        }
+        !self.stopped
+    }
+
+    // Keeps track of the best solution so far, given the moves from this point on
+    fn found<const TRACKED: bool>(&mut self, moves_to_solve: &[(Card, Direction, MoveOutcome)]) {
+        let steps = self.steps_taken + steps_of(moves_to_solve);
+        if self.best.as_ref().is_none_or(|best| steps < steps_of(best)) {
+            let mut solution = self.moves.clone();
+            solution.extend_from_slice(moves_to_solve);
+            self.improve::<TRACKED>(solution);
+        }
+    }
+
+    fn improve<const TRACKED: bool>(&mut self, solution: Solution) {
+        self.record::<TRACKED>(|stats| stats.improvements += 1);
+        if let Some(callback) = self.on_improvement.as_mut() {
+            callback(&solution);
+        }
+        self.best = Some(solution);
+    }
+
+    fn play(&mut self, card: Card, direction: Direction, outcome: MoveOutcome) {
+        self.moves.push((card, direction, outcome));
+        self.steps_taken += outcome.steps;
+    }
+
+    fn take_back(&mut self) {
+        if let Some((_, _, outcome)) = self.moves.pop() {
+            self.steps_taken -= outcome.steps;
+        }
+    }
+
+    // Remember the best solution from every (position, remaining cards) state
+    // so no state is searched twice, instead of only avoiding loops in the path
+    pub fn use_transposition_table(&mut self) {
+        self.transpositions = Some(HashMap::new());
+    }
+
+    // Other threads' solutions are only a bound on ties, so each thread still
+    // finds the same solution the serial search would in its own branches
+    fn step_bound(&self, step_count_to_beat: Option<i32>) -> Option<i32> {
+        match self.shared_bound.map(|best| best.load(Ordering::Relaxed)) {
+            Some(best) if best != i32::MAX => {
+                let shared = best + 1 - self.steps_taken;
+                Some(step_count_to_beat.map_or(shared, |local| local.min(shared)))
+            }
+            _ => step_count_to_beat,
+        }
+    }
+
+    fn known_move<const TRACKED: bool>(
+        &mut self,
+        position: Location,
+        card: Card,
+        direction: Direction,
+    ) -> Result<MoveOutcome, MoveFailure> {
+        let key = (position, card, direction);
+        let shared = self
+            .pruning
+            .transitions
+            .as_ref()
+            .and_then(|transitions| transitions.get(&key));
+        let stats = if TRACKED { self.stats.as_mut() } else { None };
+        if let Some(known_move) = shared {
+            if let Some(stats) = stats {
+                stats.known_move_hits += 1;
+            }
+            return *known_move;
+        }
+        match self.known_moves.entry(key) {
+            Entry::Occupied(known_move) => {
+                if let Some(stats) = stats {
+                    stats.known_move_hits += 1;
+                }
+                *known_move.get()
+            }
+            Entry::Vacant(unknown) => match stats {
+                Some(stats) => {
+                    let started = Instant::now();
+                    let move_result = try_move(self.map, position, card, direction);
+                    stats.try_move_calls += 1;
+                    stats.simulation_time += started.elapsed();
+                    *unknown.insert(move_result)
+                }
+                None => *unknown.insert(try_move(self.map, position, card, direction)),
+            },
+        }
+    }
+
+    fn try_moves_to_reach_hole<const TRACKED: bool>(
+        &mut self,
+        position: Location,
+        card_counts: &mut Vec<i32>,
+        previous_positions: &mut Vec<Location>,
+        mut step_count_to_beat: Option<i32>,
+    ) -> Option<Solution> {
+        if self
+            .stroke_limit
+            .is_some_and(|limit| previous_positions.len() >= limit)
+            || !self.expand_node::<TRACKED>()
+        {
+            return None;
+        }
+        previous_positions.push(position);
+        let depth = self.moves.len();
+        self.record::<TRACKED>(|stats| stats.max_depth = stats.max_depth.max(depth));
+        let mut solution: Option<Solution> = None;
+        let cards_to_use: Vec<usize> = card_counts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &count)| if count > 0 { Some(i) } else { None })
+            .collect();
+        for i in cards_to_use {
+            card_counts[i] -= 1;
+            let current_card = self.cards[i];
+            for direction in DIRECTIONS.iter() {
+                if self.stopped {
+                    break;
+                }
+                if let Ok(outcome) = self.known_move::<TRACKED>(position, current_card, *direction)
+                {
+                    let MoveOutcome {
+                        position: end_position,
+                        steps,
+                        ..
+                    } = outcome;
+                    let remaining_steps = self
+                        .step_bound(step_count_to_beat)
+                        .map(|max_steps| max_steps - steps);
+                    // Only evaluate moves that beat the target step count
+                    if remaining_steps.is_none() || remaining_steps.unwrap() > 0 {
+                        // If movement ends on the hole it must be an acceptable solution
+                        if self.map.get(&end_position).unwrap().terrain == Terrain::Hole {
+                            let moves_to_solve = vec![(current_card, *direction, outcome)];
+                            self.found::<TRACKED>(&moves_to_solve);
+                            solution = Some(moves_to_solve);
+                            step_count_to_beat = Some(steps);
+                        } else if previous_positions.contains(&end_position) {
+                            self.record::<TRACKED>(|stats| stats.cycle_prunes += 1);
+                        } else if self.cannot_beat(end_position, remaining_steps) {
+                            self.record::<TRACKED>(|stats| stats.bound_prunes += 1);
+                        } else if self.is_dead_end(end_position, card_counts) {
+                            self.record::<TRACKED>(|stats| stats.dead_end_prunes += 1);
+                        // Otherwise, keep building a path to try and reach the hole
+                        } else {
+                            self.play(current_card, *direction, outcome);
+                            let moves_to_solve = self.try_moves_to_reach_hole::<TRACKED>(
+                                end_position,
+                                card_counts,
+                                previous_positions,
+                                remaining_steps,
+                            );
+                            self.take_back();
+                            if let Some(mut moves_to_solve) = moves_to_solve {
+                                moves_to_solve.insert(0, (current_card, *direction, outcome));
+                                step_count_to_beat = Some(steps_of(&moves_to_solve));
+                                solution = Some(moves_to_solve);
+                            }
+                        }
+                    } else {
+                        self.record::<TRACKED>(|stats| stats.step_prunes += 1);
+                    }
+                }
+            }
+            card_counts[i] += 1;
+        }
+        previous_positions.pop();
+        solution
+    }
+
+    fn try_moves_from_state<const TRACKED: bool>(
+        &mut self,
+        position: Location,
+        card_counts: &mut Vec<i32>,
+        mut step_count_to_beat: Option<i32>,
+    ) -> Option<Solution> {
+        let state = (position, card_counts.clone());
+        let table = self.transpositions.as_ref().unwrap();
+        match table.get(&state) {
+            Some(Transposition::Solved(solution)) => {
+                self.transposition_hits += 1;
+                return solution
+                    .as_ref()
+                    .filter(|s| step_count_to_beat.is_none_or(|max_steps| steps_of(s) < max_steps))
+                    .cloned();
+            }
+            Some(Transposition::AtLeast(steps))
+                if step_count_to_beat.is_some_and(|max_steps| *steps >= max_steps) =>
+            {
+                self.transposition_hits += 1;
+                return None;
+            }
+            _ => (),
+        }
+        if !self.expand_node::<TRACKED>() {
+            return None;
+        }
+        let depth = self.moves.len();
+        self.record::<TRACKED>(|stats| stats.max_depth = stats.max_depth.max(depth));
+
+        let mut solution: Option<Solution> = None;
+        for i in 0..card_counts.len() {
+            if card_counts[i] == 0 {
+                continue;
+            }
+            card_counts[i] -= 1;
+            let current_card = self.cards[i];
+            for direction in DIRECTIONS.iter() {
+                if self.stopped {
+                    break;
+                }
+                if let Ok(outcome) = self.known_move::<TRACKED>(position, current_card, *direction)
+                {
+                    let remaining_steps =
+                        step_count_to_beat.map(|max_steps| max_steps - outcome.steps);
+                    if remaining_steps.is_some_and(|remaining| remaining <= 0) {
+                        self.record::<TRACKED>(|stats| stats.step_prunes += 1);
+                        continue;
+                    }
+                    let moves_to_solve =
+                        if self.map.get(&outcome.position).unwrap().terrain == Terrain::Hole {
+                            Some(Vec::new())
+                        } else if self.cannot_beat(outcome.position, remaining_steps) {
+                            self.record::<TRACKED>(|stats| stats.bound_prunes += 1);
+                            None
+                        } else if self.is_dead_end(outcome.position, card_counts) {
+                            self.record::<TRACKED>(|stats| stats.dead_end_prunes += 1);
+                            None
+                        } else {
+                            self.play(current_card, *direction, outcome);
+                            let moves_to_solve = self.try_moves_from_state::<TRACKED>(
+                                outcome.position,
+                                card_counts,
+                                remaining_steps,
+                            );
+                            self.take_back();
+                            moves_to_solve
+                        };
+                    if let Some(mut moves_to_solve) = moves_to_solve {
+                        moves_to_solve.insert(0, (current_card, *direction, outcome));
+                        self.found::<TRACKED>(&moves_to_solve);
+                        step_count_to_beat = Some(steps_of(&moves_to_solve));
+                        solution = Some(moves_to_solve);
+                    }
+                }
+            }
+            card_counts[i] += 1;
+        }
+
+        // A search that was cut short doesn't know the best solution from here
+        if self.stopped {
+            return solution;
+        }
+        // Anything better than the bound would have been found, so a solution
+        // is the best there is, but no solution only says nothing beats the bound
+        let entry = match (&solution, step_count_to_beat) {
+            (None, Some(max_steps)) => Transposition::AtLeast(max_steps),
+            _ => Transposition::Solved(solution.clone()),
+        };
+        self.transpositions.as_mut().unwrap().insert(state, entry);
+        solution
+    }
+
+    fn search_for_fewest_steps(
+        &mut self,
+        start: Location,
+        card_counts: &[i32],
+        step_count_to_beat: Option<i32>,
+    ) -> Option<Solution> {
+        self.stroke_limit = None;
+        self.best = None;
+        self.prepare_pruning(card_counts);
+        if self.tracked() {
+            self.depth_first::<true>(start, card_counts, step_count_to_beat);
+        } else {
+            self.depth_first::<false>(start, card_counts, step_count_to_beat);
+        }
+        self.best.take()
+    }
+
+    fn depth_first<const TRACKED: bool>(
+        &mut self,
+        start: Location,
+        card_counts: &[i32],
+        step_count_to_beat: Option<i32>,
+    ) {
+        if self.transpositions.is_some() {
+            self.try_moves_from_state::<TRACKED>(
+                start,
+                &mut card_counts.to_vec(),
+                step_count_to_beat,
+            );
+        } else {
+            self.try_moves_to_reach_hole::<TRACKED>(
+                start,
+                &mut card_counts.to_vec(),
+                &mut Vec::new(),
+                step_count_to_beat,
+            );
+        }
+    }
+
+    // Depth first search for the fewest steps, however many cards it takes
+    pub fn fewest_steps(&mut self, start: Location, card_counts: &[i32]) -> Option<Solution> {
+        self.start_counting();
+        self.search_for_fewest_steps(start, card_counts, None)
+    }
+
+    // Searches from both ends for the fewest steps, joining the two halves
+    // where they meet. Stops early the same way the depth first search does
+    pub fn fewest_steps_bidirectional(
+        &mut self,
+        start: Location,
+        card_counts: &[i32],
+    ) -> Option<Solution> {
+        self.start_counting();
+        self.prepare_transitions();
+        if self.stopped {
+            return None;
+        }
+        let transitions = self.pruning.transitions.clone().unwrap();
+        let (map, cards, tracked) = (self.map, self.cards, self.tracked());
+        bidirectional::fewest_steps(map, start, cards, card_counts, &transitions, &mut || {
+            if tracked {
+                self.expand_node::<true>()
+            } else {
+                self.expand_node::<false>()
+            }
+        })
+    }
+
+    // A* search for the fewest steps, guided by the same relaxed distances
+    // used as a lower bound. Stops early the same way the depth first search does
+    pub fn fewest_steps_astar(&mut self, start: Location, card_counts: &[i32]) -> Option<Solution> {
+        self.start_counting();
+        self.prepare_transitions();
+        if self.stopped {
+            return None;
+        }
+        let transitions = self.pruning.transitions.clone().unwrap();
+        let lower_bounds = self.pruning.lower_bounds.clone().unwrap();
+        let (map, cards, tracked) = (self.map, self.cards, self.tracked());
+        astar::fewest_steps(
+            map,
+            start,
+            cards,
+            card_counts,
+            &transitions,
+            &lower_bounds,
+            &mut || {
+                if tracked {
+                    self.expand_node::<true>()
+                } else {
+                    self.expand_node::<false>()
+                }
+            },
+        )
+    }
+
+    // Iterative deepening on the number of cards, so the first solution found
+    // uses as few cards as possible (and the fewest steps for that many cards)
+    pub fn fewest_strokes(&mut self, start: Location, card_counts: &[i32]) -> Option<Solution> {
+        let total_cards: i32 = card_counts.iter().sum();
+        self.start_counting();
+        self.prepare_pruning(card_counts);
+        let tracked = self.tracked();
+        for limit in 1..=total_cards as usize {
+            self.stroke_limit = Some(limit);
+            self.best = None;
+            let (card_counts, previous_positions) = (&mut card_counts.to_vec(), &mut Vec::new());
+            if tracked {
+                self.try_moves_to_reach_hole::<true>(start, card_counts, previous_positions, None);
+            } else {
+                self.try_moves_to_reach_hole::<false>(start, card_counts, previous_positions, None);
+            }
+            if self.best.is_some() || self.stopped {
+                break;
+            }
+        }
+        self.stroke_limit = None;
+        self.best.take()
+    }
+
+    // Every way to reach the hole, keeping the one with the fewest steps and,
+    // of those, the first that needs the fewest rotations of the hand
+    fn try_moves_with_fewest_rotations<const TRACKED: bool>(
+        &mut self,
+        position: Location,
+        hand: &mut Vec<Card>,
+        card_counts: &mut Vec<i32>,
+        previous_positions: &mut Vec<Location>,
+        rotations: usize,
+        best_rotations: &mut usize,
+    ) {
+        if !self.expand_node::<TRACKED>() {
+            return;
+        }
+        previous_positions.push(position);
+        for i in 0..self.cards.len() {
+            if card_counts[i] == 0 {
+                continue;
+            }
+            let current_card = self.cards[i];
+            card_counts[i] -= 1;
+            // Which copy is taken changes how far round the hand later cards are
+            for (index, presses) in copies_of(hand, current_card) {
+                let rotations = rotations + presses;
+                hand.remove(index);
+                for direction in DIRECTIONS.iter() {
+                    if self.stopped {
+                        break;
+                    }
+                    if let Ok(outcome) =
+                        self.known_move::<TRACKED>(position, current_card, *direction)
+                    {
+                        // Fewer steps than the best so far, or as many with fewer rotations
+                        let tie_helps = rotations < *best_rotations;
+                        let steps_left = self.best.as_ref().map(|best| {
+                            steps_of(best) - self.steps_taken - outcome.steps + tie_helps as i32
+                        });
+                        if steps_left.is_some_and(|steps_left| steps_left <= 0) {
+                            self.record::<TRACKED>(|stats| stats.step_prunes += 1);
+                            continue;
+                        }
+                        if self.map.get(&outcome.position).unwrap().terrain == Terrain::Hole {
+                            let mut solution = self.moves.clone();
+                            solution.push((current_card, *direction, outcome));
+                            self.improve::<TRACKED>(solution);
+                            *best_rotations = rotations;
+                        } else if previous_positions.contains(&outcome.position) {
+                            self.record::<TRACKED>(|stats| stats.cycle_prunes += 1);
+                        } else if self.cannot_beat(outcome.position, steps_left) {
+                            self.record::<TRACKED>(|stats| stats.bound_prunes += 1);
+                        } else if self.is_dead_end(outcome.position, card_counts) {
+                            self.record::<TRACKED>(|stats| stats.dead_end_prunes += 1);
+                        } else {
+                            self.play(current_card, *direction, outcome);
+                            self.try_moves_with_fewest_rotations::<TRACKED>(
+                                outcome.position,
+                                hand,
+                                card_counts,
+                                previous_positions,
+                                rotations,
+                                best_rotations,
+                            );
+                            self.take_back();
+                        }
+                    }
+                }
+                hand.insert(index, current_card);
+            }
+            card_counts[i] += 1;
+        }
+        previous_positions.pop();
+    }
+
+    // The fewest steps, then the fewest presses of q and e to turn the hand to
+    // each card, for a hand in the order the game shows it. One search does
+    // both, so ties are settled while the steps are still being cut down
+    pub fn fewest_rotations(&mut self, start: Location, hand: &[Card]) -> Option<Solution> {
+        let card_counts: Vec<i32> = self
+            .cards
+            .iter()
+            .map(|card| hand.iter().filter(|c| *c == card).count() as i32)
+            .collect();
+        self.start_counting();
+        self.stroke_limit = None;
+        self.best = None;
+        self.prepare_pruning(&card_counts);
+        let (hand, card_counts) = (&mut hand.to_vec(), &mut card_counts.clone());
+        let mut best_rotations = usize::MAX;
+        if self.tracked() {
+            self.try_moves_with_fewest_rotations::<true>(
+                start,
+                hand,
+                card_counts,
+                &mut Vec::new(),
+                0,
+                &mut best_rotations,
+            );
+        } else {
+            self.try_moves_with_fewest_rotations::<false>(
+                start,
+                hand,
+                card_counts,
+                &mut Vec::new(),
+                0,
+                &mut best_rotations,
+            );
+        }
+        self.best.take()
+    }
+}
+
+// Splits the search for the fewest steps by its first move, sharing the best
+// solution between threads. Ties go to the earliest first move, the same
+// solution the serial search settles on. Setting the cancel token stops
+// every thread, keeping the best solutions they have found so far. With
+// prune, the threads search like Search::with_pruning
+pub fn fewest_steps_in_parallel(
+    map: &HashMap<Location, Tile>,
+    start: Location,
+    cards: &[Card],
+    card_counts: &[i32],
+    threads: usize,
+    cancel: Option<CancelToken>,
+    prune: bool,
+) -> Option<Solution> {
+    let mut first_moves = Vec::new();
+    for (i, card) in cards.iter().enumerate() {
+        if card_counts[i] > 0 {
+            for direction in DIRECTIONS.iter() {
+                if let Ok(outcome) = try_move(map, start, *card, *direction) {
+                    first_moves.push((i, *direction, outcome));
+                }
+            }
+        }
+    }
+    let best = AtomicI32::new(i32::MAX);
+    let next_move = AtomicUsize::new(0);
+    let solutions: Vec<Mutex<Option<Solution>>> =
+        first_moves.iter().map(|_| Mutex::new(None)).collect();
+    // Every thread prunes with the same tables, so they're only worked out once
+    let mut prepared = Search::new(map, cards);
+    prepared.prune = prune;
+    prepared.cancel = cancel.clone();
+    prepared.prepare_pruning(card_counts);
+    if prepared.stopped {
+        return None;
+    }
+    let pruning = prepared.pruning;
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                let mut search = Search::new(map, cards);
+                search.shared_bound = Some(&best);
+                if let Some(token) = &cancel {
+                    search.cancel_with(token.clone());
+                }
+                search.pruning = pruning.clone();
+                search.prune = prune;
+                while !search.stopped_early() {
+                    let n = next_move.fetch_add(1, Ordering::Relaxed);
+                    let (i, direction, outcome) = match first_moves.get(n) {
+                        Some(first_move) => *first_move,
+                        None => break,
+                    };
+                    let solution = if map.get(&outcome.position).unwrap().terrain == Terrain::Hole {
+                        Some(vec![(cards[i], direction, outcome)])
+                    } else if outcome.position == start {
+                        None
+                    } else {
+                        let mut card_counts = card_counts.to_vec();
+                        card_counts[i] -= 1;
+                        search.moves = vec![(cards[i], direction, outcome)];
+                        search.steps_taken = outcome.steps;
+                        search
+                            .try_moves_to_reach_hole::<false>(
+                                outcome.position,
+                                &mut card_counts,
+                                &mut vec![start],
+                                None,
+                            )
+                            .map(|mut moves| {
+                                moves.insert(0, (cards[i], direction, outcome));
+                                moves
+                            })
+                    };
+                    if let Some(solution) = solution {
+                        best.fetch_min(steps_of(&solution), Ordering::Relaxed);
+                        *solutions[n].lock().unwrap() = Some(solution);
+                    }
+                }
+            });
+        }
+    });
+    solutions
+        .into_iter()
+        .filter_map(|solution| solution.into_inner().unwrap())
+        .min_by_key(|solution| steps_of(solution))
+}
+
+// Whether a solution already found is at least as good on both counts
+fn dominated(front: &[Solution], strokes: usize, steps: i32) -> bool {
+    front
+        .iter()
+        .any(|solution| solution.len() <= strokes && steps_of(solution) <= steps)
+}
+
+impl<'a> Search<'a> {
+    fn extend_pareto_front(
+        &mut self,
+        position: Location,
+        card_counts: &mut Vec<i32>,
+        previous_positions: &mut Vec<Location>,
+        moves: &mut Solution,
+        front: &mut Vec<Solution>,
+    ) {
+        previous_positions.push(position);
+        let steps_so_far = steps_of(moves);
+        for i in 0..card_counts.len() {
+            if card_counts[i] == 0 {
+                continue;
+            }
+            card_counts[i] -= 1;
+            let current_card = self.cards[i];
+            for direction in DIRECTIONS.iter() {
+                if let Ok(outcome) = self.known_move::<false>(position, current_card, *direction) {
+                    let strokes = moves.len() + 1;
+                    let steps = steps_so_far + outcome.steps;
+                    if self.map.get(&outcome.position).unwrap().terrain == Terrain::Hole {
+                        if !dominated(front, strokes, steps) {
+                            front.retain(|s| !(strokes <= s.len() && steps <= steps_of(s)));
+                            let mut solution = moves.clone();
+                            solution.push((current_card, *direction, outcome));
+                            front.push(solution);
+                        }
+                    // Another move costs at least one card and three steps
+                    } else if !previous_positions.contains(&outcome.position)
+                        && !dominated(front, strokes + 1, steps + 3)
+                    {
+                        moves.push((current_card, *direction, outcome));
+                        self.extend_pareto_front(
+                            outcome.position,
+                            card_counts,
+                            previous_positions,
+                            moves,
+                            front,
+                        );
+                        moves.pop();
+                    }
+                }
+            }
+            card_counts[i] += 1;
+        }
+        previous_positions.pop();
+    }
+
+    // Every solution that no other solution beats on both strokes and steps,
+    // from fewest strokes to fewest steps
+    pub fn pareto_front(&mut self, start: Location, card_counts: &[i32]) -> Vec<Solution> {
+        let mut front = Vec::new();
+        self.extend_pareto_front(
+            start,
+            &mut card_counts.to_vec(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut front,
+        );
+        front.sort_by_key(|solution| solution.len());
+        front
+    }
+}
+
+// How many ways the hole can be reached, with each copy of a card counted
+// as a different card, and with identical cards counted as one
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct SolutionCount {
+    pub with_duplicates: u64,
+    pub without_duplicates: u64,
+}
+
+impl<'a> Search<'a> {
+    fn count_solutions_from(
+        &mut self,
+        position: Location,
+        card_counts: &mut Vec<i32>,
+        counted: &mut HashMap<(Location, Vec<i32>), SolutionCount>,
+    ) -> SolutionCount {
+        if let Some(count) = counted.get(&(position, card_counts.clone())) {
+            return *count;
+        }
+        let mut count = SolutionCount::default();
+        for i in 0..card_counts.len() {
+            if card_counts[i] == 0 {
+                continue;
+            }
+            let copies = card_counts[i] as u64;
+            card_counts[i] -= 1;
+            for direction in DIRECTIONS.iter() {
+                if let Ok(outcome) = self.known_move::<false>(position, self.cards[i], *direction) {
+                    let ways = if self.map.get(&outcome.position).unwrap().terrain == Terrain::Hole
+                    {
+                        SolutionCount {
+                            with_duplicates: 1,
+                            without_duplicates: 1,
+                        }
+                    } else {
+                        self.count_solutions_from(outcome.position, card_counts, counted)
+                    };
+                    count.with_duplicates = count
+                        .with_duplicates
+                        .saturating_add(copies.saturating_mul(ways.with_duplicates));
+                    count.without_duplicates = count
+                        .without_duplicates
+                        .saturating_add(ways.without_duplicates);
+                }
+            }
+            card_counts[i] += 1;
+        }
+        counted.insert((position, card_counts.clone()), count);
+        count
+    }
+
+    // Counts every sequence of moves that ends in the hole, without listing them
+    pub fn count_solutions(&mut self, start: Location, card_counts: &[i32]) -> SolutionCount {
+        self.count_solutions_from(start, &mut card_counts.to_vec(), &mut HashMap::new())
+    }
+
+    fn list_solutions_from(
+        &mut self,
+        position: Location,
+        card_counts: &mut Vec<i32>,
+        moves: &mut Solution,
+        solutions: &mut Vec<Solution>,
+        limit: Option<usize>,
+    ) {
+        for i in 0..card_counts.len() {
+            if card_counts[i] == 0 {
+                continue;
+            }
+            card_counts[i] -= 1;
+            for direction in DIRECTIONS.iter() {
+                if limit.is_some_and(|limit| solutions.len() >= limit) {
+                    break;
+                }
+                if let Ok(outcome) = self.known_move::<false>(position, self.cards[i], *direction) {
+                    moves.push((self.cards[i], *direction, outcome));
+                    if self.map.get(&outcome.position).unwrap().terrain == Terrain::Hole {
+                        solutions.push(moves.clone());
+                    } else {
+                        self.list_solutions_from(
+                            outcome.position,
+                            card_counts,
+                            moves,
+                            solutions,
+                            limit,
+                        );
+                    }
+                    moves.pop();
+                }
+            }
+            card_counts[i] += 1;
+        }
+    }
+
+    // Every sequence of moves that ends in the hole, up to the limit if there is one.
+    // Identical cards are only listed once, see ways_to_play for how many copies there are
+    pub fn all_solutions(
+        &mut self,
+        start: Location,
+        card_counts: &[i32],
+        limit: Option<usize>,
+    ) -> Vec<Solution> {
+        let mut solutions = Vec::new();
+        self.list_solutions_from(
+            start,
+            &mut card_counts.to_vec(),
+            &mut Vec::new(),
+            &mut solutions,
+            limit,
+        );
+        solutions
+    }
+}
+
+// How many different ways a solution can be played when the hand has duplicate cards
+pub fn ways_to_play(
+    solution: &[(Card, Direction, MoveOutcome)],
+    cards: &[Card],
+    card_counts: &[i32],
+) -> u64 {
+    let mut card_counts = card_counts.to_vec();
+    let mut ways: u64 = 1;
+    for (card, _, _) in solution {
+        let i = cards.iter().position(|c| c == card).unwrap();
+        ways = ways.saturating_mul(card_counts[i] as u64);
+        card_counts[i] -= 1;
+    }
+    ways
+}
+
+pub fn solve(
+    map: &HashMap<Location, Tile>,
+    start: Location,
+    cards: &[Card],
+    card_counts: &[i32],
+    objective: Objective,
+) -> Option<Solution> {
+    let mut search = Search::new(map, cards);
+    match objective {
+        Objective::Steps => search.fewest_steps(start, card_counts),
+        Objective::Strokes => search.fewest_strokes(start, card_counts),
+        // Without the order of the hand, take the cards in the order given
+        Objective::Rotations => {
+            let hand: Vec<Card> = cards
+                .iter()
+                .zip(card_counts)
+                .flat_map(|(card, count)| std::iter::repeat_n(*card, *count as usize))
+                .collect();
+            search.fewest_rotations(start, &hand)
+        }
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_solver {
+    use super::*;
+    use crate::MoveEvents;
+
+    // The long card bounces back into the hole, but takes more steps than two short ones
+    fn level() -> (HashMap<Location, Tile>, Vec<Card>, Vec<i32>) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 4, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 5, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 1, corner: None });
+        (map, vec![Card { airborne: 0, rolling: 7 }, Card { airborne: 0, rolling: 1 }], vec![1, 2])
+    }
+
+    #[test]
+    fn steps_objective_prefers_fewer_steps() {
+        let (map, cards, counts) = level();
+
+        let solution = solve(&map, Location { x: 0, y: 0 }, &cards, &counts, Objective::Steps).unwrap();
+
+        assert_eq!(solution.len(), 2);
+        assert_eq!(steps_of(&solution), 8);
+    }
+
+    #[test]
+    fn strokes_objective_prefers_fewer_cards() {
+        let (map, cards, counts) = level();
+
+        let solution = solve(&map, Location { x: 0, y: 0 }, &cards, &counts, Objective::Strokes).unwrap();
+
+        assert_eq!(solution.len(), 1);
+        assert_eq!(steps_of(&solution), 10);
+        assert_eq!(solution[0].0, Card { airborne: 0, rolling: 7 });
+    }
+
+    #[test]
+    fn pareto_front_lists_each_trade_off() {
+        let (map, cards, counts) = level();
+
+        let front = Search::new(&map, &cards).pareto_front(Location { x: 0, y: 0 }, &counts);
+
+        assert_eq!(front.iter().map(|s| (s.len(), steps_of(s))).collect::<Vec<_>>(), vec![(1, 10), (2, 8)]);
+    }
+
+    #[test]
+    fn pareto_front_drops_dominated_solutions() {
+        let (map, _, _) = level();
+        let cards = vec![Card { airborne: 0, rolling: 2 }, Card { airborne: 0, rolling: 1 }];
+
+        let front = Search::new(&map, &cards).pareto_front(Location { x: 0, y: 0 }, &[1, 2]);
+
+        assert_eq!(front.iter().map(|s| (s.len(), steps_of(s))).collect::<Vec<_>>(), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn lists_every_solution() {
+        let (map, cards, counts) = level();
+
+        let solutions = Search::new(&map, &cards).all_solutions(Location { x: 0, y: 0 }, &counts, None);
+        let listed: Vec<Vec<String>> = solutions.iter().map(|s| s.iter().map(|(c, d, _)| crate::describe_move(c, d)).collect()).collect();
+
+        assert_eq!(listed, vec![
+            vec!["Use 0/7 right"],
+            vec!["Use 0/1 right", "Use 0/7 right", "Use 0/1 right"],
+            vec!["Use 0/1 right", "Use 0/1 left", "Use 0/7 right"],
+            vec!["Use 0/1 right", "Use 0/1 right"],
+        ]);
+        assert_eq!(solutions.iter().map(|s| ways_to_play(s, &cards, &counts)).collect::<Vec<_>>(), vec![1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn caps_the_number_of_solutions_listed() {
+        let (map, cards, counts) = level();
+
+        assert_eq!(Search::new(&map, &cards).all_solutions(Location { x: 0, y: 0 }, &counts, Some(2)).len(), 2);
+    }
+
+    #[test]
+    fn counts_solutions_with_and_without_duplicate_cards() {
+        let (map, cards, counts) = level();
+
+        let count = Search::new(&map, &cards).count_solutions(Location { x: 0, y: 0 }, &counts);
+
+        assert_eq!(count, SolutionCount { with_duplicates: 7, without_duplicates: 4 });
+    }
+
+    // An open field, so there are plenty of solutions with the same number of steps
+    fn open_field() -> (HashMap<Location, Tile>, Vec<Card>, Vec<i32>) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                map.insert(Location { x, y }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+            }
+        }
+        map.insert(Location { x: 2, y: 3 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 2 }, Tile { terrain: Terrain::Ground, elevation: 1, corner: None });
+        let cards = vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 1, rolling: 1 }, Card { airborne: 0, rolling: 2 }];
+        (map, cards, vec![2, 1, 2])
+    }
+
+    #[test]
+    fn parallel_search_matches_serial_search() {
+        let (map, cards, counts) = open_field();
+
+        for start in map.keys().copied().filter(|l| *l != Location { x: 2, y: 3 }) {
+            let serial = solve(&map, start, &cards, &counts, Objective::Steps);
+            for threads in 1..5 {
+                let parallel = fewest_steps_in_parallel(&map, start, &cards, &counts, threads, None, true);
+
+                assert_eq!(parallel, serial, "from {:?} with {} threads", start, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_same_solutions_with_pruning() {
+        let (map, cards, counts) = open_field();
+
+        for start in map.keys().copied().filter(|l| map[l].terrain != Terrain::Hole) {
+            let unpruned = solve(&map, start, &cards, &counts, Objective::Steps);
+            let mut search = Search::new(&map, &cards);
+            search.with_pruning();
+            let pruned = search.fewest_steps(start, &counts);
+            let parallel = fewest_steps_in_parallel(&map, start, &cards, &counts, 2, None, true);
+
+            assert!(search.pruning.transitions.is_some());
+            assert_eq!(pruned, unpruned, "from {:?}", start);
+            assert_eq!(parallel.as_ref().map(|s| steps_of(s)), unpruned.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+        }
+    }
+
+    #[test]
+    fn transposition_table_finds_solutions_just_as_good() {
+        let (map, cards, counts) = open_field();
+
+        let mut hits = 0;
+        for start in map.keys().copied().filter(|l| *l != Location { x: 2, y: 3 }) {
+            let serial = solve(&map, start, &cards, &counts, Objective::Steps);
+            let mut search = Search::new(&map, &cards);
+            search.use_transposition_table();
+            let with_table = search.fewest_steps(start, &counts);
+
+            assert_eq!(with_table.as_ref().map(|s| steps_of(s)), serial.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+            hits += search.transposition_hits;
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn reports_each_better_solution_as_it_is_found() {
+        let (map, cards, counts) = open_field();
+        let mut improvements = Vec::new();
+
+        let mut search = Search::new(&map, &cards);
+        search.on_improvement(|solution| improvements.push(steps_of(solution)));
+        let solution = search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        drop(search);
+
+        assert!(improvements.len() > 1);
+        assert!(improvements.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(improvements.last(), solution.as_ref().map(|s| steps_of(s)).as_ref());
+    }
+
+    #[test]
+    fn returns_best_solution_so_far_when_out_of_nodes() {
+        let (map, cards, counts) = open_field();
+        let best = solve(&map, Location { x: 0, y: 0 }, &cards, &counts, Objective::Steps).unwrap();
+
+        let mut search = Search::new(&map, &cards);
+        search.stop_after(None, Some(10));
+        let solution = search.fewest_steps(Location { x: 0, y: 0 }, &counts).unwrap();
+
+        assert!(search.stopped_early());
+        assert!(steps_of(&solution) > steps_of(&best));
+    }
+
+    #[test]
+    fn cancelling_from_progress_callback_keeps_best_so_far() {
+        let (map, cards, counts) = open_field();
+        let token = Arc::new(AtomicBool::new(false));
+        let mut reports = Vec::new();
+
+        let mut search = Search::new(&map, &cards);
+        search.cancel_with(token.clone());
+        search.on_progress(5, |progress| {
+            reports.push(progress.clone());
+            if progress.nodes >= 10 {
+                token.store(true, Ordering::Relaxed);
+            }
+        });
+        let solution = search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let stopped = search.stopped_early();
+        drop(search);
+
+        // Reports come every 5 nodes, and none after the one that cancelled
+        let nodes: Vec<u64> = reports.iter().map(|p| p.nodes).collect();
+        assert!(stopped);
+        assert!(nodes.iter().all(|n| n % 5 == 0));
+        assert!(nodes.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(nodes.iter().filter(|&&n| n >= 10).count(), 1);
+        assert!(steps_of(&solution.unwrap()) <= reports.last().unwrap().best_steps.unwrap());
+        assert!(reports.iter().all(|p| p.depth > 0));
+    }
+
+    #[test]
+    fn cancelling_before_search_stops_while_preparing() {
+        let (map, cards, counts) = open_field();
+        let token = Arc::new(AtomicBool::new(true));
+
+        let mut search = Search::new(&map, &cards);
+        search.cancel_with(token.clone());
+        let steps = search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let strokes = search.fewest_strokes(Location { x: 0, y: 0 }, &counts);
+        let astar = search.fewest_steps_astar(Location { x: 0, y: 0 }, &counts);
+        let bidirectional = search.fewest_steps_bidirectional(Location { x: 0, y: 0 }, &counts);
+
+        assert_eq!(steps, None);
+        assert_eq!(strokes, None);
+        assert_eq!(astar, None);
+        assert_eq!(bidirectional, None);
+        assert!(search.stopped_early());
+        assert!(search.pruning.transitions.is_none());
+    }
+
+    #[test]
+    fn cancelled_parallel_search_returns_at_once() {
+        let (map, cards, counts) = open_field();
+        let token = Arc::new(AtomicBool::new(true));
+
+        let solution = fewest_steps_in_parallel(&map, Location { x: 0, y: 0 }, &cards, &counts, 2, Some(token), true);
+
+        assert_eq!(solution, None);
+    }
+
+    #[test]
+    fn only_reports_stats_when_collecting_them() {
+        let (map, cards, counts) = level();
+
+        let mut untracked = Search::new(&map, &cards);
+        untracked.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let mut search = Search::new(&map, &cards);
+        search.collect_stats();
+        search.with_pruning();
+        search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let stats = search.stats.as_ref().unwrap();
+
+        assert_eq!(untracked.report_stats(), None);
+        assert_eq!(untracked.nodes, 0);
+        // Each of the 2 cards played 4 ways from each of the 6 tiles, worked out up front
+        assert_eq!(stats.try_move_calls, 48);
+        assert!(stats.known_move_hits > 0);
+        assert_eq!(stats.max_depth, 1);
+        assert!(search.report_stats().unwrap().contains("Cycle prunes: "));
+    }
+
+    #[test]
+    fn counts_each_search_from_zero() {
+        let (map, cards, counts) = level();
+        let mut search = Search::new(&map, &cards);
+        search.collect_stats();
+
+        search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let first = search.stats.as_ref().unwrap();
+        let (first_nodes, first_lookups) = (search.nodes, first.known_move_hits + first.try_move_calls);
+        search.fewest_steps(Location { x: 0, y: 0 }, &counts);
+        let stats = search.stats.as_ref().unwrap();
+
+        assert!(first_nodes > 0);
+        assert_eq!(search.nodes, first_nodes);
+        // The second search finds every move already simulated
+        assert_eq!(stats.known_move_hits, first_lookups);
+        assert_eq!(stats.try_move_calls, 0);
+    }
+
+    #[test]
+    fn lower_bound_pruning_keeps_the_same_solution() {
+        let (map, cards, counts) = open_field();
+
+        for start in map.keys().copied().filter(|l| *l != Location { x: 2, y: 3 }) {
+            let without_bounds = Search::new(&map, &cards).try_moves_to_reach_hole::<false>(start, &mut counts.clone(), &mut Vec::new(), None);
+            let mut search = Search::new(&map, &cards);
+            search.with_pruning();
+            let with_bounds = search.fewest_steps(start, &counts);
+
+            assert_eq!(with_bounds, without_bounds, "from {:?}", start);
+        }
+    }
+
+    // Whether the moves can be played from the start with the cards given, ending in the hole
+    fn plays_out(map: &HashMap<Location, Tile>, cards: &[Card], counts: &[i32], start: Location, solution: &Solution) -> bool {
+        let hand: Vec<Card> = cards.iter().zip(counts).flat_map(|(card, count)| std::iter::repeat_n(*card, *count as usize)).collect();
+        let moves: Vec<(Card, Direction)> = solution.iter().map(|(card, direction, _)| (*card, *direction)).collect();
+        let replay = crate::verify::replay(map, &hand, start, &moves);
+        replay.error.is_none() && replay.in_hole(map) && replay.strokes == *solution
+    }
+
+    #[test]
+    fn other_strategies_match_depth_first_search() {
+        for (map, cards, counts) in [level(), open_field()] {
+            for start in map.keys().copied().filter(|l| map[l].terrain != Terrain::Hole) {
+                let depth_first = Search::new(&map, &cards).fewest_steps(start, &counts);
+                let bidirectional = Search::new(&map, &cards).fewest_steps_bidirectional(start, &counts);
+                let astar = Search::new(&map, &cards).fewest_steps_astar(start, &counts);
+
+                // Ties can be broken differently, but never with more steps
+                assert_eq!(bidirectional.as_ref().map(|s| steps_of(s)), depth_first.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+                assert_eq!(astar.as_ref().map(|s| steps_of(s)), depth_first.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+                assert!(bidirectional.is_none_or(|s| plays_out(&map, &cards, &counts, start, &s)), "from {:?}", start);
+                assert!(astar.is_none_or(|s| plays_out(&map, &cards, &counts, start, &s)), "from {:?}", start);
+            }
+        }
+    }
+
+    #[test]
+    fn bidirectional_path_replays_into_hole_with_fewest_steps() {
+        for (map, cards, counts) in [level(), open_field()] {
+            let hand: Vec<Card> = cards.iter().zip(&counts).flat_map(|(card, count)| std::iter::repeat_n(*card, *count as usize)).collect();
+            for start in map.keys().copied().filter(|l| map[l].terrain != Terrain::Hole) {
+                let depth_first = Search::new(&map, &cards).fewest_steps(start, &counts);
+                let bidirectional = Search::new(&map, &cards).fewest_steps_bidirectional(start, &counts);
+                assert_eq!(bidirectional.is_some(), depth_first.is_some(), "from {:?}", start);
+
+                if let (Some(bidirectional), Some(depth_first)) = (bidirectional, depth_first) {
+                    // Play the moves found without trusting the outcomes the search reported
+                    let moves: Vec<(Card, Direction)> = bidirectional.iter().map(|(card, direction, _)| (*card, *direction)).collect();
+                    let replay = crate::verify::replay(&map, &hand, start, &moves);
+                    assert!(replay.error.is_none(), "from {:?}", start);
+                    assert!(replay.in_hole(&map), "from {:?}", start);
+                    assert_eq!(replay.steps(), steps_of(&depth_first), "from {:?}", start);
+                    assert_eq!(replay.strokes, bidirectional, "from {:?}", start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn other_strategies_stop_when_cancelled() {
+        let (map, cards, counts) = open_field();
+        let token = Arc::new(AtomicBool::new(true));
+
+        let mut search = Search::new(&map, &cards);
+        search.cancel_with(token);
+
+        assert_eq!(search.fewest_steps_bidirectional(Location { x: 0, y: 0 }, &counts), None);
+        assert!(search.stopped_early());
+        assert_eq!(search.fewest_steps_astar(Location { x: 0, y: 0 }, &counts), None);
+        assert!(search.stopped_early());
+    }
+
+    #[test]
+    fn astar_stops_when_out_of_nodes() {
+        let (map, cards, counts) = open_field();
+
+        let mut search = Search::new(&map, &cards);
+        search.stop_after(None, Some(1));
+
+        assert_eq!(search.fewest_steps_astar(Location { x: 0, y: 0 }, &counts), None);
+        assert!(search.stopped_early());
+    }
+
+    #[test]
+    fn astar_finds_a_solution_with_the_fewest_steps() {
+        let (map, cards, counts) = open_field();
+        let mut search = Search::new(&map, &cards);
+        search.prepare_transitions();
+
+        for start in map.keys().copied().filter(|l| map[l].terrain != Terrain::Hole) {
+            let depth_first = solve(&map, start, &cards, &counts, Objective::Steps);
+            let astar = astar::fewest_steps(&map, start, &cards, &counts, search.pruning.transitions.as_ref().unwrap(), search.pruning.lower_bounds.as_ref().unwrap(), &mut || true);
+
+            assert_eq!(astar.as_ref().map(|s| steps_of(s)), depth_first.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+            if let Some(astar) = astar {
+                let moves: Vec<(Card, Direction)> = astar.iter().map(|(c, d, _)| (*c, *d)).collect();
+                let replay = crate::verify::replay(&map, &[cards[0], cards[0], cards[1], cards[2], cards[2]], start, &moves);
+                assert!(replay.error.is_none() && replay.in_hole(&map));
+            }
+        }
+    }
+
+    #[test]
+    fn strokes_objective_finds_nothing_if_unsolvable() {
+        let (map, cards, _) = level();
+
+        assert!(solve(&map, Location { x: 0, y: 0 }, &cards, &[0, 1], Objective::Strokes).is_none());
+    }
+
+    #[test]
+    fn turns_the_hand_the_shorter_way() {
+        let a = Card { airborne: 0, rolling: 1 };
+        let b = Card { airborne: 0, rolling: 2 };
+        let c = Card { airborne: 1, rolling: 1 };
+
+        assert_eq!(rotations_to(&[a, b, c, a], b), (1, 1));
+        assert_eq!(rotations_to(&[a, b, c, a], c), (2, 2));
+        assert_eq!(rotations_to(&[a, b, c, b, a], c), (2, 2));
+        assert_eq!(rotations_to(&[a, b, c, a, b], b), (1, 1));
+        assert_eq!(rotations_to(&[a, a, b, a, c], c), (4, 1));
+    }
+
+    #[test]
+    fn turns_the_hand_to_the_nearest_copy_of_a_card() {
+        let a = Card { airborne: 0, rolling: 1 };
+        let b = Card { airborne: 0, rolling: 2 };
+        let c = Card { airborne: 1, rolling: 1 };
+        let d = Card { airborne: 1, rolling: 2 };
+        let e = Card { airborne: 2, rolling: 1 };
+
+        // One press of q back to the last a, not three of e to the first
+        assert_eq!(rotations_to(&[b, c, d, a, e, a], a), (5, 1));
+        assert_eq!(rotations_to(&[a, b, c, d, a], a), (0, 0));
+        assert_eq!(rotations_to(&[b, a, c, d, a, e], a), (1, 1));
+    }
+
+    #[test]
+    fn plans_which_copy_of_each_card_to_take() {
+        let a = Card { airborne: 0, rolling: 1 };
+        let b = Card { airborne: 0, rolling: 2 };
+        let play = |card| (card, Direction::North, MoveOutcome { position: Location { x: 0, y: 0 }, steps: 1, events: MoveEvents::default() });
+
+        // The nearest b leaves the other b a press away, the last b leaves it first
+        assert_eq!(rotation_plan(&[a, b, a, b], &[play(b), play(a), play(b)]), vec![(3, 1), (0, 0), (0, 0)]);
+        assert_eq!(rotations_of(&[a, b, a, b], &[play(b), play(a), play(b)]), 1);
+        assert_eq!(rotations_of(&[b, a, b, a], &[play(a), play(a)]), 2);
+    }
+
+    #[test]
+    fn rotations_objective_needs_fewest_presses_for_fewest_steps() {
+        let (map, cards, counts) = open_field();
+        let hand = vec![cards[0], cards[1], cards[2], cards[0], cards[2]];
+
+        for start in map.keys().copied().filter(|l| *l != Location { x: 2, y: 3 }) {
+            let fewest_steps = solve(&map, start, &cards, &counts, Objective::Steps);
+            let solution = Search::new(&map, &cards).fewest_rotations(start, &hand);
+            let fewest_rotations = Search::new(&map, &cards)
+                .all_solutions(start, &counts, None)
+                .iter()
+                .filter(|s| Some(steps_of(s)) == fewest_steps.as_ref().map(|s| steps_of(s)))
+                .map(|s| rotations_of(&hand, s))
+                .min();
+
+            assert_eq!(solution.as_ref().map(|s| steps_of(s)), fewest_steps.as_ref().map(|s| steps_of(s)), "from {:?}", start);
+            assert_eq!(solution.as_ref().map(|s| rotations_of(&hand, s)), fewest_rotations, "from {:?}", start);
+        }
+        let fewest_steps = solve(&map, Location { x: 1, y: 2 }, &cards, &counts, Objective::Steps).unwrap();
+        let solution = Search::new(&map, &cards).fewest_rotations(Location { x: 1, y: 2 }, &hand).unwrap();
+        assert_eq!((rotations_of(&hand, &fewest_steps), rotations_of(&hand, &solution)), (2, 0));
+    }
+}
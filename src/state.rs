@@ -0,0 +1,149 @@
+use crate::{count_cards, Card, Location, Terrain, Tile};
+use std::collections::HashMap;
+
+// Where the ball is partway through a level, and the cards still in the hand,
+// in the order the game shows them
+#[derive(PartialEq, Debug)]
+pub struct MidLevelState {
+    pub position: Location,
+    pub hand: Vec<Card>,
+}
+
+// Reads a location like "3,4"
+pub fn parse_location(text: &str) -> Result<Location, String> {
+    let (x, y) = text
+        .trim()
+        .split_once(',')
+        .ok_or(format!("Expected a location like 3,4, got \"{}\"", text))?;
+    match (x.trim().parse::<i32>(), y.trim().parse::<i32>()) {
+        (Ok(x), Ok(y)) => Ok(Location { x, y }),
+        _ => Err(format!("Invalid location \"{}\"", text)),
+    }
+}
+
+// Reads a hand like "0/1,0/2"
+pub fn parse_hand(text: &str) -> Result<Vec<Card>, String> {
+    text.split(',')
+        .filter(|card| !card.trim().is_empty())
+        .map(|card| {
+            let card = card.trim();
+            let (airborne, rolling) = card
+                .split_once('/')
+                .ok_or(format!("Expected a card like 0/2, got \"{}\"", card))?;
+            match (airborne.parse::<i32>(), rolling.parse::<i32>()) {
+                (Ok(airborne), Ok(rolling)) => Ok(Card { airborne, rolling }),
+                _ => Err(format!("Invalid card \"{}\"", card)),
+            }
+        })
+        .collect()
+}
+
+// A level document can end with a fourth section giving the state to start
+// from: the ball's location, then the cards left in the hand, one per line
+pub fn read_state_section(buffer: &str) -> Result<Option<MidLevelState>, String> {
+    let section = match buffer.trim_end().split("\n\n").nth(3) {
+        Some(section) => section,
+        None => return Ok(None),
+    };
+    let mut lines = section.lines();
+    let position = parse_location(lines.next().unwrap_or(""))?;
+    let hand = lines
+        .map(|line| parse_hand(&line.replacen(',', "/", 1)))
+        .collect::<Result<Vec<Vec<Card>>, String>>()?
+        .concat();
+    Ok(Some(MidLevelState { position, hand }))
+}
+
+// The state to start solving from. The command line takes priority over the
+// level document, and either can give just the location or just the hand
+pub fn starting_state(
+    map: &HashMap<Location, Tile>,
+    level_cards: &[Card],
+    level_start: Location,
+    from_document: Option<MidLevelState>,
+    start_flag: Option<&str>,
+    hand_flag: Option<&str>,
+) -> Result<MidLevelState, String> {
+    let mut state = from_document.unwrap_or(MidLevelState {
+        position: level_start,
+        hand: level_cards.to_vec(),
+    });
+    if let Some(start) = start_flag {
+        state.position = parse_location(start)?;
+    }
+    if let Some(hand) = hand_flag {
+        state.hand = parse_hand(hand)?;
+    }
+
+    if !map.contains_key(&state.position) {
+        return Err(format!(
+            "{},{} is not a tile in the level",
+            state.position.x, state.position.y
+        ));
+    }
+    if map[&state.position].terrain == Terrain::Hole {
+        return Err(format!(
+            "The ball is already in the hole at {},{}",
+            state.position.x, state.position.y
+        ));
+    }
+    // The hand can only have lost cards since the level started
+    let (level_unique, level_counts) = count_cards(level_cards);
+    let (unique, counts) = count_cards(&state.hand);
+    for (card, count) in unique.iter().zip(&counts) {
+        let available = level_unique
+            .iter()
+            .position(|c| c == card)
+            .map_or(0, |i| level_counts[i]);
+        if *count > available {
+            return Err(format!(
+                "The level only has {} of the card {}/{}",
+                available, card.airborne, card.rolling
+            ));
+        }
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_state {
+    use super::*;
+
+    fn level() -> (HashMap<Location, Tile>, Vec<Card>, Location) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        (map, vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 2 }], Location { x: 0, y: 0 })
+    }
+
+    #[test]
+    fn reads_state_from_level_document() {
+        let state = read_state_section("ground,0,0\nhole,1,0\n\n0,1\n0,2\n\n0,0\n\n1,0\n0,1\n").unwrap();
+
+        assert_eq!(state, Some(MidLevelState { position: Location { x: 1, y: 0 }, hand: vec![Card { airborne: 0, rolling: 1 }] }));
+        assert_eq!(read_state_section("ground,0,0\nhole,1,0\n\n0,1\n\n0,0\n").unwrap(), None);
+    }
+
+    #[test]
+    fn command_line_overrides_level_document() {
+        let (map, cards, start) = level();
+        let from_document = MidLevelState { position: Location { x: 1, y: 0 }, hand: vec![Card { airborne: 0, rolling: 2 }] };
+
+        let state = starting_state(&map, &cards, start, Some(from_document), None, Some("0/1, 0/1")).unwrap();
+
+        assert_eq!(state, MidLevelState { position: Location { x: 1, y: 0 }, hand: vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 1 }] });
+    }
+
+    #[test]
+    fn rejects_states_the_level_cant_reach() {
+        let (map, cards, start) = level();
+
+        assert!(starting_state(&map, &cards, start, None, Some("5,5"), None).is_err());
+        assert!(starting_state(&map, &cards, start, None, Some("2,0"), None).is_err());
+        assert!(starting_state(&map, &cards, start, None, None, Some("0/2,0/2")).is_err());
+        assert!(starting_state(&map, &cards, start, None, None, Some("1/1")).is_err());
+        assert!(starting_state(&map, &cards, start, None, Some("1,0"), Some("")).is_ok());
+    }
+}
@@ -0,0 +1,449 @@
+/*
+Randomised checks of try_move over small generated maps. Each property runs
+against a few hundred maps from a fixed seed, and a failing case is shrunk
+(removing tiles, flattening terrain, shortening the card) until it is as small
+as it can be while still failing.
+*/
+
+use super::*;
+use std::sync::mpsc;
+use std::time::Duration;
+
+const CASES: u64 = 500;
+
+#[derive(Clone, Debug)]
+struct Case {
+    tiles: Vec<(Location, Tile)>,
+    start: Location,
+    card: Card,
+    direction: Direction,
+}
+
+impl Case {
+    fn map(&self) -> HashMap<Location, Tile> {
+        self.tiles.iter().copied().collect()
+    }
+
+    fn try_move(&self) -> Result<MoveOutcome, MoveFailure> {
+        try_move(&self.map(), self.start, self.card, self.direction)
+    }
+}
+
+// xorshift64*, good enough to generate maps without pulling in a crate
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> i32 {
+        (self.next() % n) as i32
+    }
+
+    fn direction(&mut self) -> Direction {
+        [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ][self.below(4) as usize]
+    }
+
+    fn corner(&mut self) -> Corner {
+        [
+            Corner::Northeast,
+            Corner::Southeast,
+            Corner::Southwest,
+            Corner::Northwest,
+        ][self.below(4) as usize]
+    }
+}
+
+fn generate(seed: u64) -> Case {
+    let mut random = Random(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+    let width = 1 + random.below(5);
+    let height = 1 + random.below(5);
+    let mut tiles: Vec<(Location, Tile)> = Vec::new();
+    for x in 0..width {
+        for y in 0..height {
+            if random.below(10) < 2 {
+                continue;
+            }
+            let terrain = match random.below(20) {
+                0 => Terrain::Hole,
+                1 | 2 => Terrain::Slope(random.direction()),
+                3 => Terrain::Trap,
+                4 => Terrain::Quicksand,
+                5 => Terrain::Water,
+                6 => Terrain::Spring,
+                7 => Terrain::Conveyor(random.direction()),
+                8 | 9 => Terrain::Ice,
+                _ => Terrain::Ground,
+            };
+            let corner = match terrain {
+                Terrain::Ground | Terrain::Ice if random.below(5) == 0 => Some(random.corner()),
+                _ => None,
+            };
+            let elevation = random.below(3);
+            tiles.push((
+                Location { x, y },
+                Tile {
+                    terrain,
+                    elevation,
+                    corner,
+                },
+            ));
+        }
+    }
+    if tiles.is_empty() {
+        tiles.push((
+            Location { x: 0, y: 0 },
+            Tile {
+                terrain: Terrain::Ground,
+                elevation: 0,
+                corner: None,
+            },
+        ));
+    }
+
+    // Pair up a couple of portals
+    if tiles.len() > 3 && random.below(3) == 0 {
+        let a = random.below(tiles.len() as u64) as usize;
+        let b = random.below(tiles.len() as u64) as usize;
+        if a != b {
+            let (location_a, location_b) = (tiles[a].0, tiles[b].0);
+            tiles[a].1 = Tile {
+                terrain: Terrain::Portal(location_b),
+                elevation: tiles[a].1.elevation,
+                corner: None,
+            };
+            tiles[b].1 = Tile {
+                terrain: Terrain::Portal(location_a),
+                elevation: tiles[b].1.elevation,
+                corner: None,
+            };
+        }
+    }
+
+    // The ball can only be at rest on some kinds of tiles
+    for (_, tile) in tiles.iter_mut() {
+        if random.below(4) == 0 {
+            tile.terrain = Terrain::Ground;
+        }
+    }
+    let resting: Vec<Location> = tiles
+        .iter()
+        .filter(|(_, tile)| {
+            matches!(
+                tile.terrain,
+                Terrain::Ground
+                    | Terrain::Trap
+                    | Terrain::Spring
+                    | Terrain::Portal(_)
+                    | Terrain::Ice
+            )
+        })
+        .map(|(location, _)| *location)
+        .collect();
+    let start = if resting.is_empty() {
+        tiles[0].1.terrain = Terrain::Ground;
+        tiles[0].0
+    } else {
+        resting[random.below(resting.len() as u64) as usize]
+    };
+
+    Case {
+        tiles,
+        start,
+        card: Card {
+            airborne: random.below(5),
+            rolling: random.below(6),
+        },
+        direction: random.direction(),
+    }
+}
+
+// Every simpler version of a case, the shrinker keeps the first that still fails
+fn simplifications(case: &Case) -> Vec<Case> {
+    let mut simpler = Vec::new();
+    for i in 0..case.tiles.len() {
+        let (location, tile) = case.tiles[i];
+        if location != case.start {
+            let mut without = case.clone();
+            without.tiles.remove(i);
+            for (_, other) in without.tiles.iter_mut() {
+                if other.terrain == Terrain::Portal(location) {
+                    other.terrain = Terrain::Ground;
+                }
+            }
+            simpler.push(without);
+        }
+        if tile.terrain != Terrain::Ground && !matches!(tile.terrain, Terrain::Portal(_)) {
+            let mut flattened = case.clone();
+            flattened.tiles[i].1.terrain = Terrain::Ground;
+            simpler.push(flattened);
+        }
+        if tile.elevation != 0 {
+            let mut lowered = case.clone();
+            lowered.tiles[i].1.elevation = 0;
+            simpler.push(lowered);
+        }
+        if tile.corner.is_some() {
+            let mut uncornered = case.clone();
+            uncornered.tiles[i].1.corner = None;
+            simpler.push(uncornered);
+        }
+    }
+    if case.card.airborne > 0 {
+        let mut shorter = case.clone();
+        shorter.card.airborne -= 1;
+        simpler.push(shorter);
+    }
+    if case.card.rolling > 0 {
+        let mut shorter = case.clone();
+        shorter.card.rolling -= 1;
+        simpler.push(shorter);
+    }
+    simpler
+}
+
+fn shrink(mut case: Case, property: fn(&Case) -> Result<(), String>) -> (Case, String) {
+    let mut message = property(&case).unwrap_err();
+    'shrinking: loop {
+        for candidate in simplifications(&case) {
+            if let Err(candidate_message) = property(&candidate) {
+                case = candidate;
+                message = candidate_message;
+                continue 'shrinking;
+            }
+        }
+        return (case, message);
+    }
+}
+
+fn check(generator: fn(u64) -> Case, property: fn(&Case) -> Result<(), String>) {
+    for seed in 0..CASES {
+        let case = generator(seed);
+        if property(&case).is_err() {
+            let (minimal, message) = shrink(case, property);
+            panic!("{} (seed {})\n{:#?}", message, seed, minimal);
+        }
+    }
+}
+
+fn rotate_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::East,
+        Direction::East => Direction::South,
+        Direction::South => Direction::West,
+        Direction::West => Direction::North,
+    }
+}
+
+fn rotate_location(location: Location) -> Location {
+    Location {
+        x: location.y,
+        y: -location.x,
+    }
+}
+
+fn rotate_corner(corner: Corner) -> Corner {
+    match corner {
+        Corner::Northeast => Corner::Southeast,
+        Corner::Southeast => Corner::Southwest,
+        Corner::Southwest => Corner::Northwest,
+        Corner::Northwest => Corner::Northeast,
+    }
+}
+
+fn mirror_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::East => Direction::West,
+        Direction::West => Direction::East,
+        other => other,
+    }
+}
+
+fn mirror_location(location: Location) -> Location {
+    Location {
+        x: -location.x,
+        y: location.y,
+    }
+}
+
+fn mirror_corner(corner: Corner) -> Corner {
+    match corner {
+        Corner::Northeast => Corner::Northwest,
+        Corner::Northwest => Corner::Northeast,
+        Corner::Southeast => Corner::Southwest,
+        Corner::Southwest => Corner::Southeast,
+    }
+}
+
+// Moves the whole case (map, start, and direction) to a new orientation
+fn transform(
+    case: &Case,
+    location: fn(Location) -> Location,
+    direction: fn(Direction) -> Direction,
+    corner: fn(Corner) -> Corner,
+) -> Case {
+    Case {
+        tiles: case
+            .tiles
+            .iter()
+            .map(|(l, tile)| {
+                let terrain = match tile.terrain {
+                    Terrain::Slope(d) => Terrain::Slope(direction(d)),
+                    Terrain::Conveyor(d) => Terrain::Conveyor(direction(d)),
+                    Terrain::Portal(exit) => Terrain::Portal(location(exit)),
+                    other => other,
+                };
+                (
+                    location(*l),
+                    Tile {
+                        terrain,
+                        elevation: tile.elevation,
+                        corner: tile.corner.map(corner),
+                    },
+                )
+            })
+            .collect(),
+        start: location(case.start),
+        card: case.card,
+        direction: direction(case.direction),
+    }
+}
+
+fn transform_result(
+    result: Result<MoveOutcome, MoveFailure>,
+    location: fn(Location) -> Location,
+) -> Result<MoveOutcome, MoveFailure> {
+    match result {
+        Ok(outcome) => Ok(MoveOutcome {
+            position: location(outcome.position),
+            ..outcome
+        }),
+        Err(MoveFailure::RolledOffMap(l)) => Err(MoveFailure::RolledOffMap(location(l))),
+        Err(MoveFailure::FlewOffMap(l)) => Err(MoveFailure::FlewOffMap(location(l))),
+        Err(MoveFailure::SankInQuicksand(l)) => Err(MoveFailure::SankInQuicksand(location(l))),
+        Err(MoveFailure::StuckInLoop(l)) => Err(MoveFailure::StuckInLoop(location(l))),
+    }
+}
+
+fn flat_ground(seed: u64) -> Case {
+    let mut case = generate(seed);
+    for (_, tile) in case.tiles.iter_mut() {
+        *tile = Tile {
+            terrain: Terrain::Ground,
+            elevation: 0,
+            corner: None,
+        };
+    }
+    case.card.rolling = 0;
+    case
+}
+
+#[test]
+fn always_finishes_on_a_tile_of_the_map() {
+    check(generate, |case| match case.try_move() {
+        Ok(outcome) if !case.map().contains_key(&outcome.position) => {
+            Err(format!("finished off the map at {:?}", outcome.position))
+        }
+        _ => Ok(()),
+    });
+}
+
+#[test]
+fn always_takes_at_least_three_steps() {
+    check(generate, |case| match case.try_move() {
+        Ok(outcome) if outcome.steps < 3 => Err(format!("only took {} steps", outcome.steps)),
+        _ => Ok(()),
+    });
+}
+
+#[test]
+fn lands_exactly_airborne_distance_away_on_flat_ground() {
+    check(flat_ground, |case| {
+        let mut expected = case.start;
+        match case.direction {
+            Direction::North => expected.y += case.card.airborne,
+            Direction::East => expected.x += case.card.airborne,
+            Direction::South => expected.y -= case.card.airborne,
+            Direction::West => expected.x -= case.card.airborne,
+        }
+        let expected = if case.map().contains_key(&expected) {
+            Ok(expected)
+        } else {
+            Err(MoveFailure::FlewOffMap(expected))
+        };
+        let result = case.try_move().map(|outcome| outcome.position);
+        if result == expected {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, got {:?}", expected, result))
+        }
+    });
+}
+
+#[test]
+fn rotating_the_map_rotates_the_result() {
+    check(generate, |case| {
+        let rotated = transform(case, rotate_location, rotate_direction, rotate_corner);
+        let expected = transform_result(case.try_move(), rotate_location);
+        let result = rotated.try_move();
+        if result == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {:?} after rotating, got {:?}",
+                expected, result
+            ))
+        }
+    });
+}
+
+#[test]
+fn mirroring_the_map_mirrors_the_result() {
+    check(generate, |case| {
+        let mirrored = transform(case, mirror_location, mirror_direction, mirror_corner);
+        let expected = transform_result(case.try_move(), mirror_location);
+        let result = mirrored.try_move();
+        if result == expected {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {:?} after mirroring, got {:?}",
+                expected, result
+            ))
+        }
+    });
+}
+
+#[test]
+fn simulation_always_finishes() {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for seed in 0..CASES {
+            let case = generate(seed);
+            for direction in [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ]
+            .iter()
+            {
+                let _ = try_move(&case.map(), case.start, case.card, *direction);
+            }
+        }
+        sender.send(()).unwrap();
+    });
+    assert!(
+        receiver.recv_timeout(Duration::from_secs(30)).is_ok(),
+        "try_move did not finish"
+    );
+}
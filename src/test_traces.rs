@@ -0,0 +1,120 @@
+/*
+Replays the traces in traces/ through the simulator. A trace is a level in the
+same format the solver reads, followed by the move that was played and every
+tile the ball was seen on in game, one per line. Lines starting with # are
+comments.
+
+    ground,0,0,0
+    hole,1,0,0
+
+    0,1
+
+    0,0
+
+    Use 0/1 right
+
+    0,0
+    1,0
+*/
+
+use super::*;
+use crate::verify::parse_move;
+
+struct Trace {
+    map: HashMap<Location, Tile>,
+    start: Location,
+    card: Card,
+    direction: Direction,
+    path: Vec<Location>,
+}
+
+fn read_trace(text: &str) -> Result<Trace, String> {
+    let text: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+    let text = text.join("\n");
+    let sections: Vec<&str> = text.trim().split("\n\n").collect();
+    if sections.len() != 5 {
+        return Err(format!("Expected 5 sections, found {}", sections.len()));
+    }
+    let (map, _, start) = read_level(&sections[..3].join("\n\n"));
+    let (card, direction) = parse_move(sections[3])?;
+    let path = sections[4]
+        .lines()
+        .map(|line| {
+            let coords: Vec<&str> = line.trim().split(',').collect();
+            match (
+                coords.first().map(|x| x.parse()),
+                coords.get(1).map(|y| y.parse()),
+            ) {
+                (Some(Ok(x)), Some(Ok(y))) => Ok(Location { x, y }),
+                _ => Err(format!("Invalid tile \"{}\"", line)),
+            }
+        })
+        .collect::<Result<Vec<Location>, String>>()?;
+    Ok(Trace {
+        map,
+        start,
+        card,
+        direction,
+        path,
+    })
+}
+
+// Ignored until traces have been recorded in game, see traces/README.md.
+// Run it with cargo test -- --ignored once there are some
+#[test]
+#[ignore]
+fn simulator_matches_recorded_traces() {
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/traces");
+    let mut files: Vec<std::path::PathBuf> = std::fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "trace"))
+        .collect();
+    files.sort();
+    // Replaying nothing checks nothing
+    assert!(!files.is_empty(), "No traces to replay in {}", directory);
+
+    let mut differences = Vec::new();
+    for file in files {
+        let name = file.file_name().unwrap().to_string_lossy().to_string();
+        let trace = match read_trace(&std::fs::read_to_string(&file).unwrap()) {
+            Ok(trace) => trace,
+            Err(message) => {
+                differences.push(format!("{}: {}", name, message));
+                continue;
+            }
+        };
+        let (path, result) = trace_move(&trace.map, trace.start, trace.card, trace.direction);
+        match result {
+            Ok(outcome) if outcome.position != *trace.path.last().unwrap() => {
+                differences.push(format!(
+                    "{}: finished on {:?}, but the game finished on {:?}",
+                    name,
+                    outcome.position,
+                    trace.path.last().unwrap()
+                ));
+            }
+            Ok(_) => (),
+            Err(failure) => differences.push(format!("{}: {}", name, failure)),
+        }
+        if path != trace.path {
+            differences.push(format!(
+                "{}: went through {:?}, but the game went through {:?}",
+                name, path, trace.path
+            ));
+        }
+    }
+    assert!(differences.is_empty(), "\n{}\n", differences.join("\n"));
+}
+
+#[test]
+#[rustfmt::skip]
+fn reads_trace_format() {
+    let trace = read_trace("# A comment\nground,0,0,0\nhole,1,0,0\n\n0,1\n\n0,0\n\nUse 0/1 right\n\n0,0\n1,0\n").unwrap();
+
+    assert_eq!(trace.map.len(), 2);
+    assert_eq!(trace.start, Location { x: 0, y: 0 });
+    assert_eq!((trace.card, trace.direction), (Card { airborne: 0, rolling: 1 }, Direction::East));
+    assert_eq!(trace.path, vec![Location { x: 0, y: 0 }, Location { x: 1, y: 0 }]);
+    assert!(read_trace("ground,0,0,0\n\n0,1\n\n0,0\n").is_err());
+}
@@ -0,0 +1,169 @@
+use crate::MoveOutcome;
+
+// Estimates how long the game takes to play out a move, so that automation
+// scripts wait long enough before pressing the next key
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct TimingModel {
+    // Swinging the club, and the ball settling once it stops
+    pub putt: f64,
+    pub roll_tile: f64,
+    // Air time is a fixed launch/landing cost plus a cost per tile travelled
+    pub flight: f64,
+    pub airborne_tile: f64,
+    pub spring_launch: f64,
+    pub spring_stop: f64,
+    pub portal_transit: f64,
+    pub water_reset: f64,
+    // The ball creeps to a halt at the end of long moves, counted in steps
+    pub long_move_steps: i32,
+    pub long_move_slowdown: f64,
+}
+
+impl TimingModel {
+    // Known platforms, the macOS release (v3.02) is what the solver was tested against.
+    // Its timings haven't been measured yet. They reproduce the delays the
+    // AppleScript used before: a third of a second per step, plus half a second
+    // once a move is over 18 steps
+    pub fn for_platform(platform: &str) -> Option<TimingModel> {
+        match platform {
+            "macos" => Some(TimingModel {
+                putt: 1.0,
+                roll_tile: 1.0 / 3.0,
+                flight: 0.0,
+                airborne_tile: 1.0 / 3.0,
+                spring_launch: 0.0,
+                spring_stop: 1.0 / 3.0,
+                portal_transit: 1.0 / 3.0,
+                water_reset: 1.0,
+                long_move_steps: 18,
+                long_move_slowdown: 0.5,
+            }),
+            _ => None,
+        }
+    }
+
+    // Overrides parameters from a list like "roll_tile=0.3,water_reset=1.2"
+    pub fn with_overrides(mut self, overrides: &str) -> Result<TimingModel, String> {
+        for item in overrides.split(',').filter(|item| !item.is_empty()) {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or(format!("Expected key=value, got \"{}\"", item))?;
+            if key == "long_move_steps" {
+                self.long_move_steps = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("Invalid step count \"{}\"", value))?;
+                continue;
+            }
+            let seconds = value
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid duration \"{}\"", value))?;
+            match key {
+                "putt" => self.putt = seconds,
+                "roll_tile" => self.roll_tile = seconds,
+                "flight" => self.flight = seconds,
+                "airborne_tile" => self.airborne_tile = seconds,
+                "spring_launch" => self.spring_launch = seconds,
+                "spring_stop" => self.spring_stop = seconds,
+                "portal_transit" => self.portal_transit = seconds,
+                "water_reset" => self.water_reset = seconds,
+                "long_move_slowdown" => self.long_move_slowdown = seconds,
+                _ => return Err(format!("Unknown timing parameter \"{}\"", key)),
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn seconds_for(&self, outcome: &MoveOutcome) -> f64 {
+        let events = &outcome.events;
+        let mut seconds = self.putt
+            + self.roll_tile * events.rolled_tiles as f64
+            + self.flight * events.flights as f64
+            + self.airborne_tile * events.airborne_tiles as f64
+            + self.spring_launch * events.spring_launches as f64
+            + self.spring_stop * events.spring_stops as f64
+            + self.portal_transit * events.portal_transits as f64
+            + self.water_reset * events.water_resets as f64;
+        if outcome.steps > self.long_move_steps {
+            seconds += self.long_move_slowdown;
+        }
+        seconds
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_timing {
+    use super::*;
+    use crate::{try_move, Card, Direction, Location, MoveEvents, Terrain, Tile};
+    use std::collections::HashMap;
+
+    #[test]
+    fn counts_each_event_during_move() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Portal(Location { x: 1, y: 2 }), elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 2 }, Tile { terrain: Terrain::Portal(Location { x: 1, y: 0 }), elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 2 }, Tile { terrain: Terrain::Spring, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 2 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 4, y: 2 }, Tile { terrain: Terrain::Water, elevation: 0, corner: None });
+
+        let result = try_move(&map, Location { x: 0, y: 0 }, Card { rolling: 3, airborne: 1 }, Direction::East);
+
+        assert_eq!(result.unwrap().events, MoveEvents {
+            rolled_tiles: 1,
+            flights: 2,
+            airborne_tiles: 3,
+            spring_launches: 1,
+            spring_stops: 0,
+            portal_transits: 1,
+            water_resets: 1,
+        });
+    }
+
+    #[test]
+    fn matches_step_count_on_macos_for_short_moves() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Spring, elevation: 0, corner: None });
+        map.insert(Location { x: 4, y: 0 }, Tile { terrain: Terrain::Water, elevation: 0, corner: None });
+
+        let outcome = try_move(&map, Location { x: 0, y: 0 }, Card { rolling: 4, airborne: 0 }, Direction::East).unwrap();
+        let model = TimingModel::for_platform("macos").unwrap();
+
+        assert!((model.seconds_for(&outcome) - outcome.steps as f64 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn slows_down_at_end_of_long_moves() {
+        let model = TimingModel::for_platform("macos").unwrap();
+        let short = MoveOutcome { position: Location { x: 0, y: 0 }, steps: 18, events: MoveEvents { rolled_tiles: 15, ..Default::default() } };
+        let long = MoveOutcome { position: Location { x: 0, y: 0 }, steps: 19, events: MoveEvents { rolled_tiles: 16, ..Default::default() } };
+
+        assert!((model.seconds_for(&long) - model.seconds_for(&short) - (model.roll_tile + model.long_move_slowdown)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn matches_old_applescript_delays_on_macos() {
+        // Over 18 steps without a long roll, from flying and a water reset
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        for x in 1..=12 { map.insert(Location { x, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None }); }
+        map.insert(Location { x: 13, y: 0 }, Tile { terrain: Terrain::Water, elevation: 0, corner: None });
+
+        let outcome = try_move(&map, Location { x: 0, y: 0 }, Card { rolling: 3, airborne: 10 }, Direction::East).unwrap();
+        let model = TimingModel::for_platform("macos").unwrap();
+
+        assert!(outcome.steps > 18 && outcome.events.rolled_tiles < 15);
+        assert!((model.seconds_for(&outcome) - (outcome.steps as f64 / 3.0 + 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn overrides_parameters_by_name() {
+        let model = TimingModel::for_platform("macos").unwrap().with_overrides("roll_tile=0.25,long_move_steps=10").unwrap();
+
+        assert_eq!(model.roll_tile, 0.25);
+        assert_eq!(model.long_move_steps, 10);
+        assert!(TimingModel::for_platform("macos").unwrap().with_overrides("bounce=1").is_err());
+    }
+}
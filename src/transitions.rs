@@ -0,0 +1,168 @@
+use crate::json::Json;
+use crate::{
+    count_cards, name_of_direction, read_level_file, try_move, Card, Direction, KnownMoves,
+    Location, Tile, DIRECTIONS,
+};
+use std::collections::HashMap;
+
+// Every tile of the map in a stable order, so output doesn't change between runs
+pub fn sorted_locations(map: &HashMap<Location, Tile>) -> Vec<Location> {
+    let mut locations: Vec<Location> = map.keys().copied().collect();
+    locations.sort_by_key(|l| (l.x, l.y));
+    locations
+}
+
+// Simulates every card in every direction from every tile of the map
+pub fn all_transitions(map: &HashMap<Location, Tile>, cards: &[Card]) -> KnownMoves {
+    let mut transitions: KnownMoves = HashMap::new();
+    for location in sorted_locations(map) {
+        for card in cards {
+            for direction in DIRECTIONS.iter() {
+                transitions.insert(
+                    (location, *card, *direction),
+                    try_move(map, location, *card, *direction),
+                );
+            }
+        }
+    }
+    transitions
+}
+
+fn rows(
+    map: &HashMap<Location, Tile>,
+    cards: &[Card],
+    transitions: &KnownMoves,
+) -> Vec<(Location, Card, Direction)> {
+    let mut rows = Vec::new();
+    for location in sorted_locations(map) {
+        for card in cards {
+            for direction in DIRECTIONS.iter() {
+                if transitions.contains_key(&(location, *card, *direction)) {
+                    rows.push((location, *card, *direction));
+                }
+            }
+        }
+    }
+    rows
+}
+
+pub fn csv_of_transitions(
+    map: &HashMap<Location, Tile>,
+    cards: &[Card],
+    transitions: &KnownMoves,
+) -> String {
+    let mut csv = String::from(
+        "x,y,airborne,rolling,direction,end_x,end_y,steps,failure,failure_x,failure_y\n",
+    );
+    for (location, card, direction) in rows(map, cards, transitions) {
+        let prefix = format!(
+            "{},{},{},{},{}",
+            location.x,
+            location.y,
+            card.airborne,
+            card.rolling,
+            name_of_direction(&direction)
+        );
+        match transitions[&(location, card, direction)] {
+            Ok(outcome) => csv.push_str(&format!(
+                "{},{},{},{},,,\n",
+                prefix, outcome.position.x, outcome.position.y, outcome.steps
+            )),
+            Err(failure) => csv.push_str(&format!(
+                "{},,,,{},{},{}\n",
+                prefix,
+                failure.kind(),
+                failure.location().x,
+                failure.location().y
+            )),
+        }
+    }
+    csv
+}
+
+pub fn json_of_transitions(
+    map: &HashMap<Location, Tile>,
+    cards: &[Card],
+    transitions: &KnownMoves,
+) -> Json {
+    Json::Array(
+        rows(map, cards, transitions)
+            .into_iter()
+            .map(|(location, card, direction)| {
+                let mut fields = vec![
+                    (String::from("x"), Json::from(location.x)),
+                    (String::from("y"), Json::from(location.y)),
+                    (String::from("airborne"), Json::from(card.airborne)),
+                    (String::from("rolling"), Json::from(card.rolling)),
+                    (
+                        String::from("direction"),
+                        Json::from(name_of_direction(&direction)),
+                    ),
+                ];
+                match transitions[&(location, card, direction)] {
+                    Ok(outcome) => {
+                        fields.push((String::from("end_x"), Json::from(outcome.position.x)));
+                        fields.push((String::from("end_y"), Json::from(outcome.position.y)));
+                        fields.push((String::from("steps"), Json::from(outcome.steps)));
+                    }
+                    Err(failure) => {
+                        fields.push((String::from("failure"), Json::from(failure.kind())));
+                        fields.push((String::from("failure_x"), Json::from(failure.location().x)));
+                        fields.push((String::from("failure_y"), Json::from(failure.location().y)));
+                    }
+                }
+                Json::Object(fields)
+            })
+            .collect(),
+    )
+}
+
+pub fn run(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("Usage: golf-peaks transitions <level> [--json]");
+        std::process::exit(2);
+    }
+    let (map, all_cards, _) = read_level_file(&args[0]);
+    let (unique_cards, _) = count_cards(&all_cards);
+    let transitions = all_transitions(&map, &unique_cards);
+    if args.iter().any(|arg| arg == "--json") {
+        println!("{}", json_of_transitions(&map, &unique_cards, &transitions));
+    } else {
+        print!("{}", csv_of_transitions(&map, &unique_cards, &transitions));
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_transitions {
+    use super::*;
+    use crate::Terrain;
+
+    #[test]
+    fn lists_every_tile_card_and_direction() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        let cards = vec![Card { airborne: 0, rolling: 1 }];
+
+        let transitions = all_transitions(&map, &cards);
+        let csv = csv_of_transitions(&map, &cards, &transitions);
+
+        assert_eq!(transitions.len(), 8);
+        assert_eq!(csv.lines().count(), 9);
+        assert!(csv.contains("\n0,0,0,1,right,1,0,4,,,\n"));
+        assert!(csv.contains("\n0,0,0,1,left,,,,rolled_off_map,-1,0\n"));
+    }
+
+    #[test]
+    fn writes_failures_as_json() {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        let cards = vec![Card { airborne: 1, rolling: 0 }];
+
+        let transitions = all_transitions(&map, &cards);
+        let json = json_of_transitions(&map, &cards, &transitions);
+
+        assert_eq!(json.as_array().unwrap()[0].to_string(), r#"{"x":0,"y":0,"airborne":1,"rolling":0,"direction":"up","failure":"flew_off_map","failure_x":0,"failure_y":1}"#);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::json::{self, Json};
+use crate::solver::{self, Objective, Solution};
+use crate::{
+    count_cards, describe_move, direction_named, read_level_file, trace_move, try_move, Card,
+    Direction, Location, MoveFailure, MoveOutcome, Terrain, Tile,
+};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ReplayError {
+    CardNotInHand(Card),
+    MoveFailed(MoveFailure),
+    AlreadyInHole,
+}
+
+// The state of a level after playing some moves, stopping at the first problem
+pub struct Replay {
+    pub strokes: Vec<(Card, Direction, MoveOutcome)>,
+    pub position: Location,
+    pub remaining_cards: Vec<Card>,
+    pub error: Option<ReplayError>,
+}
+
+impl Replay {
+    pub fn in_hole(&self, map: &HashMap<Location, Tile>) -> bool {
+        map.get(&self.position).unwrap().terrain == Terrain::Hole
+    }
+
+    pub fn steps(&self) -> i32 {
+        self.strokes
+            .iter()
+            .map(|(_, _, outcome)| outcome.steps)
+            .sum()
+    }
+}
+
+pub fn replay(
+    map: &HashMap<Location, Tile>,
+    all_cards: &[Card],
+    starting_position: Location,
+    moves: &[(Card, Direction)],
+) -> Replay {
+    let mut replay = Replay {
+        strokes: Vec::new(),
+        position: starting_position,
+        remaining_cards: all_cards.to_vec(),
+        error: None,
+    };
+    for (card, direction) in moves {
+        if replay.in_hole(map) {
+            replay.error = Some(ReplayError::AlreadyInHole);
+            break;
+        }
+        let i = match replay.remaining_cards.iter().position(|c| c == card) {
+            Some(i) => i,
+            None => {
+                replay.error = Some(ReplayError::CardNotInHand(*card));
+                break;
+            }
+        };
+        match try_move(map, replay.position, *card, *direction) {
+            Ok(outcome) => {
+                replay.remaining_cards.remove(i);
+                replay.position = outcome.position;
+                replay.strokes.push((*card, *direction, outcome));
+            }
+            Err(failure) => {
+                replay.error = Some(ReplayError::MoveFailed(failure));
+                break;
+            }
+        }
+    }
+    replay
+}
+
+// Which of the moves went wrong while replaying them, and why
+pub fn describe_error(moves: &[(Card, Direction)], replay: &Replay) -> Option<String> {
+    let error = replay.error?;
+    let (card, direction) = moves[replay.strokes.len()];
+    let reason = match error {
+        ReplayError::CardNotInHand(_) => String::from("card is not in the hand"),
+        ReplayError::MoveFailed(failure) => failure.to_string(),
+        ReplayError::AlreadyInHole => String::from("ball is already in the hole"),
+    };
+    Some(format!("{}: {}", describe_move(&card, &direction), reason))
+}
+
+// Reads a move like "Use 0/2 right" or just "0/2 right"
+pub fn parse_move(text: &str) -> Result<(Card, Direction), String> {
+    let text = text.trim();
+    let text = text.strip_prefix("Use ").unwrap_or(text);
+    let (card, direction) = text
+        .split_once(' ')
+        .ok_or(format!("Expected a card and direction, got \"{}\"", text))?;
+    let (airborne, rolling) = card
+        .split_once('/')
+        .ok_or(format!("Expected a card like 0/2, got \"{}\"", card))?;
+    let card = Card {
+        airborne: airborne
+            .parse::<i32>()
+            .map_err(|_| format!("Invalid card \"{}\"", card))?,
+        rolling: rolling
+            .parse::<i32>()
+            .map_err(|_| format!("Invalid card \"{}\"", card))?,
+    };
+    let direction = direction_named(direction.trim())
+        .ok_or(format!("Unknown direction \"{}\"", direction.trim()))?;
+    Ok((card, direction))
+}
+
+// Reads moves from either the plain or JSON output of the solver
+pub fn parse_moves(text: &str) -> Result<Vec<(Card, Direction)>, String> {
+    let trimmed = text.trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_move)
+            .collect();
+    }
+    let document = json::parse(text)?;
+    let moves = document
+        .get("moves")
+        .unwrap_or(&document)
+        .as_array()
+        .ok_or("Expected a list of moves")?;
+    moves
+        .iter()
+        .map(|m| {
+            let card = Card {
+                airborne: m
+                    .get("airborne")
+                    .and_then(Json::as_i32)
+                    .ok_or("Move is missing its airborne distance")?,
+                rolling: m
+                    .get("rolling")
+                    .and_then(Json::as_i32)
+                    .ok_or("Move is missing its rolling distance")?,
+            };
+            let direction = m
+                .get("direction")
+                .and_then(Json::as_str)
+                .and_then(direction_named)
+                .ok_or("Move is missing a valid direction")?;
+            Ok((card, direction))
+        })
+        .collect()
+}
+
+// The solutions with the fewest strokes and with the fewest steps, which can differ
+pub fn best_solutions(
+    map: &HashMap<Location, Tile>,
+    starting_position: Location,
+    all_cards: &[Card],
+) -> Option<(Solution, Solution)> {
+    let (unique_cards, card_count) = count_cards(all_cards);
+    let fewest_strokes = solver::solve(
+        map,
+        starting_position,
+        &unique_cards,
+        &card_count,
+        Objective::Strokes,
+    )?;
+    let fewest_steps = solver::solve(
+        map,
+        starting_position,
+        &unique_cards,
+        &card_count,
+        Objective::Steps,
+    )?;
+    Some((fewest_strokes, fewest_steps))
+}
+
+// Strokes are compared against the fewest strokes, and steps against the fewest steps
+pub fn waste(result: &Replay, best: &(Solution, Solution)) -> (i32, i32) {
+    (
+        result.strokes.len() as i32 - best.0.len() as i32,
+        result.steps() - solver::steps_of(&best.1),
+    )
+}
+
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        eprintln!("Usage: golf-peaks verify <level> <moves> [--trace]");
+        std::process::exit(2);
+    }
+    let (map, all_cards, starting_position) = read_level_file(&args[0]);
+    let moves = match std::fs::read_to_string(&args[1])
+        .map_err(|error| format!("Could not read {}: {}", args[1], error))
+        .and_then(|text| parse_moves(&text))
+    {
+        Ok(moves) => moves,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let show_path = args.iter().any(|arg| arg == "--trace");
+
+    let result = replay(&map, &all_cards, starting_position, &moves);
+    let mut position = starting_position;
+    for (card, direction, outcome) in &result.strokes {
+        println!(
+            "{}: {},{} after {} steps",
+            describe_move(card, direction),
+            outcome.position.x,
+            outcome.position.y,
+            outcome.steps
+        );
+        if show_path {
+            let (path, _) = trace_move(&map, position, *card, *direction);
+            let tiles: Vec<String> = path.iter().map(|l| format!("{},{}", l.x, l.y)).collect();
+            println!("  {}", tiles.join(" -> "));
+        }
+        position = outcome.position;
+    }
+    if let Some(message) = describe_error(&moves, &result) {
+        println!("{}", message);
+    }
+    let passed = result.error.is_none() && result.in_hole(&map);
+    if passed {
+        println!(
+            "Pass in {} strokes, {} steps",
+            result.strokes.len(),
+            result.steps()
+        );
+    } else {
+        println!(
+            "Fail after {} strokes, {} steps",
+            result.strokes.len(),
+            result.steps()
+        );
+    }
+
+    // Compare against the best solutions the solver can find
+    match best_solutions(&map, starting_position, &all_cards) {
+        Some(best) => {
+            println!(
+                "Fewest strokes takes {} strokes, {} steps",
+                best.0.len(),
+                solver::steps_of(&best.0)
+            );
+            println!(
+                "Fewest steps takes {} strokes, {} steps",
+                best.1.len(),
+                solver::steps_of(&best.1)
+            );
+            if passed {
+                let (strokes, steps) = waste(&result, &best);
+                println!("Wastes {} strokes, {} steps", strokes, steps);
+            }
+        }
+        None => println!("Solver found no solution"),
+    }
+
+    if !passed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod test_verify {
+    use super::*;
+
+    fn level() -> (HashMap<Location, Tile>, Vec<Card>, Location) {
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        (map, vec![Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 2 }], Location { x: 0, y: 0 })
+    }
+
+    #[test]
+    fn reaches_hole_with_valid_moves() {
+        let (map, cards, start) = level();
+        let moves = parse_moves("Use 0/2 right\nUse 0/1 right\n").unwrap();
+
+        let result = replay(&map, &cards, start, &moves);
+
+        assert_eq!(result.error, None);
+        assert!(result.in_hole(&map));
+        assert_eq!(result.strokes.len(), 2);
+        assert_eq!(result.steps(), 9);
+    }
+
+    #[test]
+    fn stops_if_card_is_not_in_hand() {
+        let (map, cards, start) = level();
+        let moves = parse_moves("Use 0/2 right\nUse 0/2 right\n").unwrap();
+
+        let result = replay(&map, &cards, start, &moves);
+
+        assert_eq!(result.error, Some(ReplayError::CardNotInHand(Card { airborne: 0, rolling: 2 })));
+        assert_eq!(result.position, Location { x: 2, y: 0 });
+        assert_eq!(describe_error(&moves, &result), Some(String::from("Use 0/2 right: card is not in the hand")));
+    }
+
+    #[test]
+    fn stops_if_move_fails() {
+        let (map, cards, start) = level();
+        let moves = parse_moves("Use 0/2 left\n").unwrap();
+
+        let result = replay(&map, &cards, start, &moves);
+
+        assert_eq!(result.error, Some(ReplayError::MoveFailed(MoveFailure::RolledOffMap(Location { x: -1, y: 0 }))));
+    }
+
+    #[test]
+    fn stops_if_ball_is_already_in_hole() {
+        let (map, cards, start) = level();
+        let moves = parse_moves("Use 0/1 right\nUse 0/2 right\nUse 0/1 left\n").unwrap();
+
+        let result = replay(&map, &[cards[0], cards[1], cards[0]], start, &moves);
+
+        assert_eq!(result.error, Some(ReplayError::AlreadyInHole));
+    }
+
+    #[test]
+    fn compares_strokes_and_steps_against_different_solutions() {
+        // One long card bounces back into the hole, two short ones take fewer steps
+        let mut map: HashMap<Location, Tile> = HashMap::new();
+        map.insert(Location { x: 0, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 1, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 2, y: 0 }, Tile { terrain: Terrain::Hole, elevation: 0, corner: None });
+        map.insert(Location { x: 3, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 4, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 0, corner: None });
+        map.insert(Location { x: 5, y: 0 }, Tile { terrain: Terrain::Ground, elevation: 1, corner: None });
+        let cards = vec![Card { airborne: 0, rolling: 7 }, Card { airborne: 0, rolling: 1 }, Card { airborne: 0, rolling: 1 }];
+        let start = Location { x: 0, y: 0 };
+
+        let best = best_solutions(&map, start, &cards).unwrap();
+        let short = replay(&map, &cards, start, &parse_moves("0/1 right\n0/1 right\n").unwrap());
+        let long = replay(&map, &cards, start, &parse_moves("0/7 right\n").unwrap());
+
+        assert_eq!((best.0.len(), solver::steps_of(&best.1)), (1, 8));
+        assert_eq!(waste(&short, &best), (1, 0));
+        assert_eq!(waste(&long, &best), (0, 2));
+    }
+
+    #[test]
+    fn reads_moves_from_json_output() {
+        let moves = parse_moves(r#"{"strokes":1,"steps":5,"moves":[{"airborne":1,"rolling":2,"direction":"up","steps":5}]}"#).unwrap();
+
+        assert_eq!(moves, vec![(Card { airborne: 1, rolling: 2 }, Direction::North)]);
+    }
+
+    #[test]
+    fn rejects_unknown_moves() {
+        assert!(parse_moves("Use 0/2 sideways").is_err());
+        assert!(parse_moves("Solving level 01-01").is_err());
+    }
+}